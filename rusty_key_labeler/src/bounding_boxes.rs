@@ -3,7 +3,7 @@ use std::process::Command;
 use bevy::{
     color::{Color, Srgba},
     core::Name,
-    math::{Vec2, Vec3, Vec4},
+    math::{Rect, Vec2, Vec3, Vec4},
     prelude::{Commands, Component, Entity, Resource, Transform},
     render::view::RenderLayers,
 };
@@ -94,6 +94,16 @@ impl PartialOrd for BoundingBox {
     }
 }
 
+impl BoundingBox {
+    /// The box in image pixels, with y pointing down.
+    pub fn pixel_rect(&self) -> Rect {
+        Rect::from_center_size(
+            Vec2::new(self.x, self.y),
+            Vec2::new(self.width, self.height),
+        )
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Component)]
 pub struct SelectedBoundingBox;
 
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage:
+  rusty_key_labeler [--open <image>]
+      Opens the labeler, at <image> when given (a path or file stem).
+  rusty_key_labeler evaluate [--output <dir>]
+      Writes evaluation.json and evaluation.md comparing settings.predictions.labels
+      against the project's labels, into <dir> or the output path.
+  rusty_key_labeler stats [--output <dir>]
+      Writes stats.json and stats.md with class balance and box statistics,
+      into <dir> or the output path.
+  rusty_key_labeler lint
+      Lists label issues, including label files rejected when loading.";
+
+/// What the labeler was started to do.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Command {
+    Label { open: Option<PathBuf> },
+    Evaluate { output: Option<PathBuf> },
+    Stats { output: Option<PathBuf> },
+    Lint,
+}
+
+impl Command {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter().peekable();
+
+        let mut command = match args.peek().map(String::as_str) {
+            Some("evaluate") => {
+                args.next();
+                Command::Evaluate { output: None }
+            }
+            Some("stats") => {
+                args.next();
+                Command::Stats { output: None }
+            }
+            Some("lint") => {
+                args.next();
+                Command::Lint
+            }
+            _ => Command::Label { open: None },
+        };
+
+        while let Some(arg) = args.next() {
+            let value = match &mut command {
+                Command::Label { open } if arg == "--open" => open,
+                Command::Evaluate { output } | Command::Stats { output } if arg == "--output" => {
+                    output
+                }
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            };
+
+            match args.next() {
+                Some(path) => *value = Some(PathBuf::from(path)),
+                None => return Err(format!("{} needs a value", arg)),
+            }
+        }
+
+        Ok(command)
+    }
+}
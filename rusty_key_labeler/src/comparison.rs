@@ -0,0 +1,246 @@
+use bevy::{math::Rect, prelude::Resource, tasks::Task};
+use yolo_io::{ImageLabelPair, YoloEntry};
+
+use crate::predictions::{read_predicted_entries, PredictedEntry};
+
+/// How one image's predictions line up with its ground truth. Indices
+/// refer to the slices passed to `Comparison::new`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Comparison {
+    /// Ground truth index, prediction index and their IoU.
+    pub matches: Vec<(usize, usize, f32)>,
+    pub false_positives: Vec<usize>,
+    pub false_negatives: Vec<usize>,
+}
+
+impl Comparison {
+    /// Greedily matches predictions, most confident first, to the unmatched
+    /// ground truth box of the same class with the highest IoU, provided it
+    /// reaches `iou_threshold`.
+    pub fn new(
+        ground_truth: &[YoloEntry],
+        predictions: &[PredictedEntry],
+        iou_threshold: f32,
+    ) -> Self {
+        let mut order = (0..predictions.len()).collect::<Vec<usize>>();
+        order.sort_by(|a, b| {
+            predictions[*b]
+                .confidence_or_certain()
+                .total_cmp(&predictions[*a].confidence_or_certain())
+        });
+
+        let mut matched_truth = vec![false; ground_truth.len()];
+        let mut comparison = Comparison::default();
+
+        for prediction_index in order {
+            let predicted = &predictions[prediction_index].entry;
+            let best = ground_truth
+                .iter()
+                .enumerate()
+                .filter(|(truth_index, truth)| {
+                    !matched_truth[*truth_index] && truth.class == predicted.class
+                })
+                .map(|(truth_index, truth)| (truth_index, entry_iou(truth, predicted)))
+                .filter(|(_, iou)| *iou >= iou_threshold)
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+            match best {
+                Some((truth_index, iou)) => {
+                    matched_truth[truth_index] = true;
+                    comparison
+                        .matches
+                        .push((truth_index, prediction_index, iou));
+                }
+                None => comparison.false_positives.push(prediction_index),
+            }
+        }
+
+        comparison.false_negatives = matched_truth
+            .iter()
+            .enumerate()
+            .filter(|(_, matched)| !**matched)
+            .map(|(truth_index, _)| truth_index)
+            .collect();
+
+        comparison
+    }
+
+    pub fn disagreement(&self) -> usize {
+        self.false_positives.len() + self.false_negatives.len()
+    }
+}
+
+/// IoU of two entries. It is the same in normalized and pixel coordinates.
+pub fn entry_iou(a: &YoloEntry, b: &YoloEntry) -> f32 {
+    let rect = |entry: &YoloEntry| {
+        Rect::from_center_size(
+            (entry.x_center, entry.y_center).into(),
+            (entry.width, entry.height).into(),
+        )
+    };
+
+    let (a, b) = (rect(a), rect(b));
+    let intersection = a.intersect(b);
+    if intersection.is_empty() {
+        return 0.0;
+    }
+
+    let area = |rect: Rect| rect.width() * rect.height();
+    area(intersection) / (area(a) + area(b) - area(intersection))
+}
+
+/// The selected image's comparison, with the entries its indices refer to.
+#[derive(Debug, PartialEq, Clone, Default, Resource)]
+pub struct ImageComparison {
+    pub ground_truth: Vec<YoloEntry>,
+    pub predictions: Vec<PredictedEntry>,
+    pub comparison: Comparison,
+}
+
+/// Predictions for `pair` at or above `threshold`; unreadable files count as empty.
+pub fn visible_predictions(
+    directory: &str,
+    pair: &ImageLabelPair,
+    threshold: f32,
+) -> Vec<PredictedEntry> {
+    read_predicted_entries(directory, &pair.name)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|predicted| predicted.confidence_or_certain() >= threshold)
+        .collect()
+}
+
+/// Images ordered from most to least disagreement between labels and predictions.
+#[derive(Default, Resource)]
+pub struct DisagreementRanking {
+    /// Pair index and disagreement, for images with any disagreement.
+    pub order: Vec<(usize, usize)>,
+    /// Next entry of `order` to visit.
+    pub position: usize,
+    /// Confidence threshold the ranking was computed with.
+    pub threshold: Option<f32>,
+    /// Pair indices the ranking was computed over.
+    pub indices: Vec<usize>,
+    /// A ranking still being computed.
+    pub pending: Option<Task<DisagreementRanking>>,
+}
+
+impl DisagreementRanking {
+    /// Ranks the pairs at `indices`, reading their predictions from `directory`.
+    pub fn rank(
+        pairs: &[ImageLabelPair],
+        indices: &[usize],
+        directory: &str,
+        threshold: f32,
+        iou: f32,
+    ) -> Self {
+        let mut order = indices
+            .iter()
+            .filter_map(|index| pairs.get(*index).map(|pair| (*index, pair)))
+            .map(|(index, pair)| {
+                let ground_truth = pair
+                    .label_file
+                    .as_ref()
+                    .map(|label_file| label_file.entries.clone())
+                    .unwrap_or_default();
+                let predictions = visible_predictions(directory, pair, threshold);
+
+                (
+                    index,
+                    Comparison::new(&ground_truth, &predictions, iou).disagreement(),
+                )
+            })
+            .filter(|(_, disagreement)| *disagreement > 0)
+            .collect::<Vec<(usize, usize)>>();
+
+        // Stable, so ties keep project order.
+        order.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        Self {
+            order,
+            position: 0,
+            threshold: Some(threshold),
+            indices: indices.to_vec(),
+            pending: None,
+        }
+    }
+
+    /// Whether the ranking was computed for this threshold and these pairs.
+    pub fn is_current(&self, threshold: f32, indices: &[usize]) -> bool {
+        self.threshold == Some(threshold) && self.indices == indices
+    }
+
+    /// Drops the ranking and any ranking in progress, e.g. after labels change.
+    pub fn invalidate(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(class: isize, x_center: f32, y_center: f32) -> YoloEntry {
+        YoloEntry {
+            class,
+            x_center,
+            y_center,
+            width: 0.2,
+            height: 0.2,
+        }
+    }
+
+    #[test]
+    fn test_matches_by_class_and_iou() {
+        let ground_truth = vec![entry(0, 0.2, 0.2), entry(1, 0.6, 0.6), entry(0, 0.8, 0.2)];
+        let predictions = vec![
+            PredictedEntry {
+                entry: entry(0, 0.21, 0.2),
+                confidence: Some(0.9),
+            },
+            // Right place, wrong class.
+            PredictedEntry {
+                entry: entry(0, 0.6, 0.6),
+                confidence: Some(0.8),
+            },
+            PredictedEntry {
+                entry: entry(0, 0.8, 0.21),
+                confidence: None,
+            },
+        ];
+
+        let comparison = Comparison::new(&ground_truth, &predictions, 0.5);
+
+        assert_eq!(
+            comparison
+                .matches
+                .iter()
+                .map(|(truth, predicted, _)| (*truth, *predicted))
+                .collect::<Vec<_>>(),
+            vec![(2, 2), (0, 0)]
+        );
+        assert_eq!(comparison.false_positives, vec![1]);
+        assert_eq!(comparison.false_negatives, vec![1]);
+        assert_eq!(comparison.disagreement(), 2);
+    }
+
+    #[test]
+    fn test_ranking_only_covers_given_indices() {
+        let pair = |name: &str, boxes: usize| ImageLabelPair {
+            name: name.to_string(),
+            image_path: None,
+            label_file: Some(yolo_io::YoloFile {
+                stem: name.to_string(),
+                path: String::new(),
+                entries: vec![entry(0, 0.5, 0.5); boxes],
+            }),
+        };
+        let pairs = vec![pair("a", 1), pair("b", 3), pair("c", 2)];
+
+        // No prediction files, so every label is a false negative.
+        let ranking = DisagreementRanking::rank(&pairs, &[0, 2], "missing", 0.0, 0.5);
+        assert_eq!(ranking.order, vec![(2, 2), (0, 1)]);
+        assert!(ranking.is_current(0.0, &[0, 2]));
+        assert!(!ranking.is_current(0.0, &[0, 1, 2]));
+    }
+}
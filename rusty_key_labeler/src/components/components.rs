@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use yolo_io::YoloFile;
 
 #[derive(Debug, Clone, Component)]
 pub struct ImageLoading(pub Handle<Image>);
@@ -12,11 +13,6 @@ pub struct SelectedImage;
 #[derive(Debug, Clone, Component)]
 pub struct MainCamera;
 
-#[derive(Debug, Clone, Component)]
-pub struct DebounceTimer {
-    pub timer: Timer,
-}
-
 #[derive(Debug, Clone, Component)]
 pub struct FocusInViewport {
     pub width: f32,
@@ -38,3 +34,14 @@ pub struct ComputedViewport {
 
 #[derive(Debug, Clone, Default, Component)]
 pub struct UninitializedRenderTarget;
+
+/// Labels for the current image to write to disk and repaint.
+#[derive(Debug, Clone, Component)]
+pub struct LabelEditNeeded {
+    pub label_file: YoloFile,
+    /// Entry index to select once the boxes are repainted.
+    pub select: Option<usize>,
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct SelectAfterReload(pub usize);
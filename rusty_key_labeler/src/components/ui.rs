@@ -35,6 +35,69 @@ pub struct CurrentFileNameLabel;
 #[derive(Debug, Clone, Component)]
 pub struct FileNameLabelUpdateNeeded(pub String);
 
+#[derive(Debug, Clone, Component)]
+pub struct ScrubPreview;
+
+#[derive(Debug, Clone, Component)]
+pub struct ScrubPreviewImage;
+
+#[derive(Debug, Clone, Component)]
+pub struct ScrubPreviewFileName;
+
+/// Shows the scrub preview with a file name and optional thumbnail, or hides
+/// it when `file_name` is `None`.
+#[derive(Debug, Clone, Component)]
+pub struct ScrubPreviewUpdateNeeded {
+    pub file_name: Option<String>,
+    pub thumbnail: Option<Handle<Image>>,
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct CursorReadout;
+
+#[derive(Debug, Clone, Component)]
+pub struct ComparisonSummary;
+
+#[derive(Debug, Clone, Component)]
+pub struct UiMinimap;
+
+#[derive(Debug, Clone, Component)]
+pub struct MinimapBox;
+
+#[derive(Debug, Clone, Component)]
+pub struct MinimapViewportRect;
+
+#[derive(Debug, Clone, Component)]
+pub struct UiFilmstrip;
+
+/// Offset of a filmstrip slot from the current image in navigation order.
+#[derive(Debug, Clone, Component)]
+pub struct FilmstripThumbnail {
+    pub offset: isize,
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct FilmstripBadge {
+    pub offset: isize,
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct FilmstripUpdateNeeded;
+
+#[derive(Debug, Clone, Component)]
+pub struct UiStatsPanel;
+
+/// Recomputes the stats panel, e.g. after labels were saved.
+#[derive(Debug, Clone, Component)]
+pub struct StatsUpdateNeeded;
+
+#[derive(Debug, Clone, Component)]
+pub struct UiHelpOverlay;
+
+/// Rebuilds the help overlay from the current key map.
+#[derive(Debug, Clone, Component)]
+pub struct HelpUpdateNeeded;
+
 // END UI Part Markers
 
 #[derive(Debug, Clone, Resource)]
@@ -44,3 +107,75 @@ pub struct Ui {
     pub font_path: String,
     pub font_handle: Option<Handle<Font>>,
 }
+
+/// The current image's review status, beside the labeling index.
+#[derive(Debug, Clone, Component)]
+pub struct UiReviewBadge;
+
+/// Shows what is being typed while a text entry is active.
+#[derive(Debug, Clone, Component)]
+pub struct TextEntryPrompt;
+
+/// The modal layer's current mode and count, when modes are enabled.
+#[derive(Debug, Clone, Component)]
+pub struct UiModeIndicator;
+
+/// A `UiColors` entry a UI node takes one of its colors from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiColorRole {
+    Background,
+    Text,
+    InnerBorder,
+    OuterBorder,
+}
+
+impl UiColorRole {
+    pub fn color(&self, colors: &UiColors) -> Color {
+        match self {
+            UiColorRole::Background => colors.background,
+            UiColorRole::Text => colors.text,
+            UiColorRole::InnerBorder => colors.inner_border,
+            UiColorRole::OuterBorder => colors.outer_border,
+        }
+    }
+}
+
+/// Which `UiColors` entries and what fraction of the configured font size a
+/// UI node was spawned with, so a config reload can restyle it in place.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct UiStyle {
+    pub background: Option<UiColorRole>,
+    pub border: Option<UiColorRole>,
+    pub text: Option<UiColorRole>,
+    pub font_scale: f32,
+}
+
+impl UiStyle {
+    /// A panel with the background color and the given border.
+    pub fn panel(border: UiColorRole) -> Self {
+        Self {
+            background: Some(UiColorRole::Background),
+            border: Some(border),
+            ..Default::default()
+        }
+    }
+
+    /// Text in the given color at `font_scale` times the configured size.
+    pub fn text(color: UiColorRole, font_scale: f32) -> Self {
+        Self {
+            text: Some(color),
+            font_scale,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_background(mut self, background: Option<UiColorRole>) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_border(mut self, border: Option<UiColorRole>) -> Self {
+        self.border = border;
+        self
+    }
+}
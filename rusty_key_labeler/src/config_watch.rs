@@ -0,0 +1,186 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use bevy::{
+    prelude::Resource,
+    time::{Timer, TimerMode},
+};
+
+use crate::resources::Config;
+
+/// Seconds between checks of the config file's modification time.
+pub const CONFIG_POLL_SECONDS: f32 = 1.0;
+
+pub fn read_config(path: &str) -> Result<(Config, String), Box<dyn std::error::Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let config: Config = serde_yml::from_str(&data)?;
+    Ok((config, data))
+}
+
+/// Polls the config file so edits apply without restarting.
+#[derive(Debug, Clone, Resource)]
+pub struct ConfigWatch {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub timer: Timer,
+}
+
+impl ConfigWatch {
+    pub fn new(path: &str) -> Self {
+        let path = PathBuf::from(path);
+        Self {
+            modified: modified_time(&path),
+            path,
+            timer: Timer::from_seconds(CONFIG_POLL_SECONDS, TimerMode::Repeating),
+        }
+    }
+
+    /// Whether the file was modified since the last call that returned true.
+    pub fn changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Checks a reloaded config against the running project. Settings that are
+/// only read at startup, like the project and output path, are kept from
+/// `current` with a note that they need a restart. Errors reject the reload.
+pub fn merge_reloaded_config(
+    current: &Config,
+    mut reloaded: Config,
+) -> Result<(Config, Vec<String>), String> {
+    let mut notes = vec![];
+
+    // The project config has no `PartialEq`; values compare maps by content.
+    if serde_json::to_value(&reloaded.project_config).ok()
+        != serde_json::to_value(&current.project_config).ok()
+    {
+        notes.push("project_config changes need a restart".to_string());
+        reloaded.project_config = current.project_config.clone();
+    }
+
+    if reloaded.output_path != current.output_path {
+        notes.push("output_path changes need a restart".to_string());
+        reloaded.output_path = current.output_path.clone();
+    }
+
+    let (settings, reloaded_settings) = (&current.settings, &mut reloaded.settings);
+    keep_startup_setting(
+        "settings.navigation_filter",
+        &settings.navigation_filter,
+        &mut reloaded_settings.navigation_filter,
+        &mut notes,
+    );
+    keep_startup_setting(
+        "settings.prefetch.memory_budget_mb",
+        &settings.prefetch.memory_budget_mb,
+        &mut reloaded_settings.prefetch.memory_budget_mb,
+        &mut notes,
+    );
+    keep_startup_setting(
+        "settings.filmstrip",
+        &settings.filmstrip,
+        &mut reloaded_settings.filmstrip,
+        &mut notes,
+    );
+    keep_startup_setting(
+        "settings.minimap",
+        &settings.minimap,
+        &mut reloaded_settings.minimap,
+        &mut notes,
+    );
+    keep_startup_setting(
+        "settings.predictions.labels",
+        &settings.predictions.labels,
+        &mut reloaded_settings.predictions.labels,
+        &mut notes,
+    );
+    keep_startup_setting(
+        "settings.predictions.confidence_threshold",
+        &settings.predictions.confidence_threshold,
+        &mut reloaded_settings.predictions.confidence_threshold,
+        &mut notes,
+    );
+
+    let class_colors = reloaded.settings.bounding_boxes.class_color_map.len();
+    let mut uncolored = reloaded
+        .project_config
+        .export
+        .class_map
+        .keys()
+        .filter(|class| **class < 0 || **class as usize >= class_colors)
+        .collect::<Vec<_>>();
+    uncolored.sort();
+    if !uncolored.is_empty() {
+        return Err(format!(
+            "class_color_map has {} colors, classes {:?} have none",
+            class_colors, uncolored
+        ));
+    }
+
+    notes.extend(
+        [
+            reloaded.settings.key_map.conflicts(),
+            reloaded
+                .settings
+                .modal
+                .conflicts(&reloaded.settings.key_map),
+        ]
+        .concat()
+        .into_iter()
+        .map(|conflict| format!("Key binding conflict: {}", conflict)),
+    );
+
+    Ok((reloaded, notes))
+}
+
+fn keep_startup_setting<T: PartialEq + Clone>(
+    name: &str,
+    current: &T,
+    reloaded: &mut T,
+    notes: &mut Vec<String>,
+) {
+    if reloaded != current {
+        notes.push(format!("{} changes need a restart", name));
+        *reloaded = current.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_project_and_rejects_missing_colors() {
+        let mut current = Config::default();
+        current
+            .project_config
+            .export
+            .class_map
+            .insert(0, "cat".to_string());
+
+        let mut reloaded = current.clone();
+        reloaded.output_path = "elsewhere".to_string();
+        reloaded.settings.zoom_factor = 3.0;
+        reloaded.settings.filmstrip.neighbours += 1;
+
+        let (merged, notes) = merge_reloaded_config(&current, reloaded.clone()).unwrap();
+        assert_eq!(merged.output_path, current.output_path);
+        assert_eq!(merged.settings.filmstrip, current.settings.filmstrip);
+        assert_eq!(merged.settings.zoom_factor, 3.0);
+        assert_eq!(notes.len(), 2);
+
+        reloaded.settings.bounding_boxes.class_color_map.clear();
+        assert!(merge_reloaded_config(&current, reloaded).is_err());
+    }
+}
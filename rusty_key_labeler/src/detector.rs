@@ -0,0 +1,136 @@
+//! Protocol for external detectors. The configured command is started once
+//! per image and receives one line of JSON on stdin:
+//!
+//! ```json
+//! {"image_path": "/data/sheet.png"}
+//! ```
+//!
+//! It prints its detections as JSON on stdout and exits. Box coordinates are
+//! normalized like YOLO labels:
+//!
+//! ```json
+//! {"boxes": [{"class": 0, "confidence": 0.92, "x_center": 0.5, "y_center": 0.25, "width": 0.2, "height": 0.1}]}
+//! ```
+
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    math::{Rect, Vec2},
+    prelude::Resource,
+    tasks::Task,
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+pub type DetectorResult = Result<DetectorResponse, Box<dyn std::error::Error + Send + Sync>>;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DetectorRequest {
+    pub image_path: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DetectorResponse {
+    pub boxes: Vec<DetectedBox>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DetectedBox {
+    pub class: isize,
+    pub confidence: f32,
+    pub x_center: f32,
+    pub y_center: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl DetectedBox {
+    pub fn pixel_rect(&self, image_size: Vec2) -> Rect {
+        Rect::from_center_size(
+            Vec2::new(self.x_center, self.y_center) * image_size,
+            Vec2::new(self.width, self.height) * image_size,
+        )
+    }
+}
+
+/// Detector processes still running, by the image they were started for.
+#[derive(Default, Resource)]
+pub struct DetectorTasks {
+    pub pending: HashMap<PathBuf, Task<DetectorResult>>,
+}
+
+/// How often a running detector is checked for having exited.
+const DETECTOR_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Runs the detector on one image, blocking until it exits. A detector
+/// still running after `timeout` is killed and reported as an error.
+pub fn run_detector(
+    command: &str,
+    args: &[String],
+    image_path: &Path,
+    timeout: Duration,
+) -> DetectorResult {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let request = serde_json::to_string(&DetectorRequest {
+        image_path: image_path.to_path_buf(),
+    })?;
+
+    // Dropping stdin closes it, telling the detector the request is complete.
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", request)?;
+    }
+
+    // Read on their own threads so a detector filling a pipe can't block.
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let started_at = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if started_at.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+            return Err(format!("detector timed out after {:.1}s", timeout.as_secs_f32()).into());
+        }
+
+        thread::sleep(DETECTOR_POLL_INTERVAL);
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(format!(
+            "detector exited with {}: {}",
+            status,
+            String::from_utf8_lossy(&stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(serde_json::from_slice(&stdout)?)
+}
+
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut data = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut data);
+        }
+        data
+    })
+}
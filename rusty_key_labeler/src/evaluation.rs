@@ -0,0 +1,411 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use hashbrown::HashMap;
+use serde::Serialize;
+use yolo_io::{ImageLabelPair, YoloEntry, YoloProject};
+
+use crate::{
+    comparison::Comparison,
+    predictions::{read_predicted_entries, PredictedEntry},
+    resources::Config,
+    settings::PredictionSettings,
+};
+
+pub const REPORT_JSON_FILE_NAME: &str = "evaluation.json";
+pub const REPORT_MARKDOWN_FILE_NAME: &str = "evaluation.md";
+
+/// One image's ground truth and predictions.
+#[derive(Debug, Clone)]
+pub struct ImagePredictions {
+    pub name: String,
+    pub image_path: Option<PathBuf>,
+    pub ground_truth: Vec<YoloEntry>,
+    pub predictions: Vec<PredictedEntry>,
+}
+
+impl ImagePredictions {
+    pub fn load(pair: &ImageLabelPair, directory: &str) -> Result<Self, String> {
+        Ok(Self {
+            name: pair.name.clone(),
+            image_path: pair.image_path.clone(),
+            ground_truth: pair
+                .label_file
+                .as_ref()
+                .map(|label_file| label_file.entries.clone())
+                .unwrap_or_default(),
+            predictions: read_predicted_entries(directory, &pair.name)?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ClassEvaluation {
+    pub class: isize,
+    pub name: String,
+    pub ground_truth: usize,
+    pub predictions: usize,
+    /// At the confidence threshold and match IoU.
+    pub precision: f32,
+    pub recall: f32,
+    pub ap50: f32,
+    /// AP averaged over IoU thresholds 0.50, 0.55, ..., 0.95.
+    pub ap50_95: f32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ImageEvaluation {
+    pub name: String,
+    pub image_path: Option<PathBuf>,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct EvaluationReport {
+    pub images: usize,
+    pub confidence_threshold: f32,
+    pub match_iou: f32,
+    pub classes: Vec<ClassEvaluation>,
+    /// Means over classes with ground truth.
+    pub map50: f32,
+    pub map50_95: f32,
+    /// Images with the most false positives and negatives, worst first.
+    pub worst_images: Vec<ImageEvaluation>,
+}
+
+impl EvaluationReport {
+    pub fn new(
+        images: &[ImagePredictions],
+        class_map: &HashMap<isize, String>,
+        settings: &PredictionSettings,
+        worst_images: usize,
+    ) -> Self {
+        let mut ground_truth = BTreeMap::<isize, usize>::new();
+        let mut predictions = BTreeMap::<isize, usize>::new();
+        for class in class_map.keys() {
+            ground_truth.insert(*class, 0);
+        }
+        for image in images {
+            for entry in image.ground_truth.iter() {
+                *ground_truth.entry(entry.class).or_default() += 1;
+            }
+            for predicted in image.predictions.iter() {
+                *predictions.entry(predicted.entry.class).or_default() += 1;
+                ground_truth.entry(predicted.entry.class).or_default();
+            }
+        }
+
+        let at_match_iou = scored_predictions(images, settings.match_iou);
+        let at_iou = (0..10)
+            .map(|step| scored_predictions(images, 0.5 + 0.05 * step as f32))
+            .collect::<Vec<_>>();
+
+        let classes = ground_truth
+            .iter()
+            .map(|(class, truth_count)| {
+                let ap_at = |scored: &BTreeMap<isize, Vec<(f32, bool)>>| {
+                    average_precision(scored.get(class).cloned().unwrap_or_default(), *truth_count)
+                };
+
+                let (true_positives, false_positives) = at_match_iou
+                    .get(class)
+                    .map(|scored| {
+                        scored
+                            .iter()
+                            .filter(|(confidence, _)| *confidence >= settings.confidence_threshold)
+                            .fold((0, 0), |(tp, fp), (_, hit)| match hit {
+                                true => (tp + 1, fp),
+                                false => (tp, fp + 1),
+                            })
+                    })
+                    .unwrap_or((0, 0));
+
+                ClassEvaluation {
+                    class: *class,
+                    name: class_map
+                        .get(class)
+                        .cloned()
+                        .unwrap_or_else(|| class.to_string()),
+                    ground_truth: *truth_count,
+                    predictions: predictions.get(class).copied().unwrap_or(0),
+                    precision: ratio(true_positives, true_positives + false_positives),
+                    recall: ratio(true_positives, *truth_count),
+                    ap50: ap_at(&at_iou[0]),
+                    ap50_95: at_iou.iter().map(ap_at).sum::<f32>() / at_iou.len() as f32,
+                }
+            })
+            .collect::<Vec<ClassEvaluation>>();
+
+        let evaluated = classes
+            .iter()
+            .filter(|class| class.ground_truth > 0)
+            .collect::<Vec<&ClassEvaluation>>();
+        let mean = |ap: fn(&ClassEvaluation) -> f32| match evaluated.is_empty() {
+            true => 0.0,
+            false => evaluated.iter().map(|class| ap(class)).sum::<f32>() / evaluated.len() as f32,
+        };
+
+        let mut worst = images
+            .iter()
+            .map(|image| {
+                let visible = image
+                    .predictions
+                    .iter()
+                    .filter(|predicted| {
+                        predicted.confidence_or_certain() >= settings.confidence_threshold
+                    })
+                    .cloned()
+                    .collect::<Vec<PredictedEntry>>();
+                let comparison = Comparison::new(&image.ground_truth, &visible, settings.match_iou);
+
+                ImageEvaluation {
+                    name: image.name.clone(),
+                    image_path: image.image_path.clone(),
+                    false_positives: comparison.false_positives.len(),
+                    false_negatives: comparison.false_negatives.len(),
+                }
+            })
+            .filter(|image| image.false_positives + image.false_negatives > 0)
+            .collect::<Vec<ImageEvaluation>>();
+
+        // Stable, so ties keep project order.
+        worst.sort_by_key(|image| std::cmp::Reverse(image.false_positives + image.false_negatives));
+        worst.truncate(worst_images);
+
+        Self {
+            images: images.len(),
+            confidence_threshold: settings.confidence_threshold,
+            match_iou: settings.match_iou,
+            map50: mean(|class| class.ap50),
+            map50_95: mean(|class| class.ap50_95),
+            classes,
+            worst_images: worst,
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![
+            "# Evaluation report".to_string(),
+            String::new(),
+            format!(
+                "{} images, confidence >= {:.2}, match IoU {:.2}",
+                self.images, self.confidence_threshold, self.match_iou
+            ),
+            String::new(),
+            format!("mAP@0.5: {:.3}  ", self.map50),
+            format!("mAP@0.5:0.95: {:.3}", self.map50_95),
+            String::new(),
+            "| Class | Labels | Predictions | Precision | Recall | AP@0.5 | AP@0.5:0.95 |"
+                .to_string(),
+            "|---|---:|---:|---:|---:|---:|---:|".to_string(),
+        ];
+
+        for class in self.classes.iter() {
+            lines.push(format!(
+                "| {} ({}) | {} | {} | {:.3} | {:.3} | {:.3} | {:.3} |",
+                class.name,
+                class.class,
+                class.ground_truth,
+                class.predictions,
+                class.precision,
+                class.recall,
+                class.ap50,
+                class.ap50_95
+            ));
+        }
+
+        lines.extend([String::new(), "## Worst images".to_string(), String::new()]);
+
+        if self.worst_images.is_empty() {
+            lines.push("Labels and predictions agree on every image.".to_string());
+        } else {
+            lines.push("| Image | FP | FN | Open |".to_string());
+            lines.push("|---|---:|---:|---|".to_string());
+            for image in self.worst_images.iter() {
+                let open = image.image_path.as_ref().map_or(String::new(), |path| {
+                    format!("`rusty_key_labeler --open {}`", path.display())
+                });
+                lines.push(format!(
+                    "| {} | {} | {} | {} |",
+                    image.name, image.false_positives, image.false_negatives, open
+                ));
+            }
+        }
+
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+/// Evaluates `predictions.labels` against the project's labels and writes
+/// the report as JSON and Markdown into `output_dir`.
+pub fn write_evaluation_report(
+    config: &Config,
+    output_dir: &Path,
+) -> Result<EvaluationReport, Box<dyn Error>> {
+    let settings = &config.settings.predictions;
+    let directory = settings
+        .labels
+        .as_ref()
+        .ok_or("settings.predictions.labels is not set")?;
+
+    let project = YoloProject::new(&config.project_config)?;
+    let images = project
+        .get_valid_pairs()
+        .iter()
+        .map(|pair| ImagePredictions::load(pair, directory))
+        .collect::<Result<Vec<ImagePredictions>, String>>()?;
+
+    let report = EvaluationReport::new(
+        &images,
+        &config.project_config.export.class_map,
+        settings,
+        settings.report_worst_images,
+    );
+
+    std::fs::create_dir_all(output_dir)?;
+    std::fs::write(
+        output_dir.join(REPORT_JSON_FILE_NAME),
+        serde_json::to_string_pretty(&report)?,
+    )?;
+    std::fs::write(
+        output_dir.join(REPORT_MARKDOWN_FILE_NAME),
+        report.to_markdown(),
+    )?;
+
+    Ok(report)
+}
+
+/// Each prediction's confidence and whether it matched a labeled box at `iou`, by class.
+fn scored_predictions(images: &[ImagePredictions], iou: f32) -> BTreeMap<isize, Vec<(f32, bool)>> {
+    let mut scored = BTreeMap::<isize, Vec<(f32, bool)>>::new();
+
+    for image in images {
+        let comparison = Comparison::new(&image.ground_truth, &image.predictions, iou);
+        let hits = comparison
+            .matches
+            .iter()
+            .map(|(_, prediction_index, _)| (*prediction_index, true));
+        let misses = comparison
+            .false_positives
+            .iter()
+            .map(|prediction_index| (*prediction_index, false));
+
+        for (prediction_index, hit) in hits.chain(misses) {
+            let predicted = &image.predictions[prediction_index];
+            scored
+                .entry(predicted.entry.class)
+                .or_default()
+                .push((predicted.confidence_or_certain(), hit));
+        }
+    }
+
+    scored
+}
+
+/// Area under the precision envelope sampled at 101 recall points, as in COCO.
+pub fn average_precision(mut scored: Vec<(f32, bool)>, ground_truth: usize) -> f32 {
+    if ground_truth == 0 || scored.is_empty() {
+        return 0.0;
+    }
+
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    let mut true_positives = 0;
+    let mut curve = scored
+        .iter()
+        .enumerate()
+        .map(|(rank, (_, hit))| {
+            if *hit {
+                true_positives += 1;
+            }
+            (
+                ratio(true_positives, ground_truth),
+                ratio(true_positives, rank + 1),
+            )
+        })
+        .collect::<Vec<(f32, f32)>>();
+
+    for index in (0..curve.len() - 1).rev() {
+        curve[index].1 = curve[index].1.max(curve[index + 1].1);
+    }
+
+    (0..=100)
+        .map(|step| {
+            let recall = step as f32 / 100.0;
+            curve
+                .iter()
+                .find(|(reached, _)| *reached >= recall)
+                .map_or(0.0, |(_, precision)| *precision)
+        })
+        .sum::<f32>()
+        / 101.0
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f32 {
+    match denominator {
+        0 => 0.0,
+        _ => numerator as f32 / denominator as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(class: isize, x_center: f32) -> YoloEntry {
+        YoloEntry {
+            class,
+            x_center,
+            y_center: 0.5,
+            width: 0.1,
+            height: 0.1,
+        }
+    }
+
+    fn predicted(class: isize, x_center: f32, confidence: f32) -> PredictedEntry {
+        PredictedEntry {
+            entry: entry(class, x_center),
+            confidence: Some(confidence),
+        }
+    }
+
+    #[test]
+    fn test_evaluates_per_class_and_ranks_worst_images() {
+        let images = vec![
+            ImagePredictions {
+                name: "agrees".to_string(),
+                image_path: None,
+                ground_truth: vec![entry(0, 0.2), entry(1, 0.6)],
+                predictions: vec![predicted(0, 0.2, 0.9), predicted(1, 0.6, 0.8)],
+            },
+            ImagePredictions {
+                name: "disagrees".to_string(),
+                image_path: None,
+                ground_truth: vec![entry(0, 0.2), entry(0, 0.6)],
+                predictions: vec![predicted(0, 0.2, 0.7), predicted(0, 0.9, 0.95)],
+            },
+        ];
+        let class_map = HashMap::from([(0, "a".to_string()), (1, "b".to_string())]);
+
+        let report = EvaluationReport::new(&images, &class_map, &PredictionSettings::default(), 5);
+
+        let class_a = &report.classes[0];
+        assert_eq!((class_a.ground_truth, class_a.predictions), (3, 3));
+        assert!((class_a.precision - 2.0 / 3.0).abs() < 1e-5);
+        assert!((class_a.recall - 2.0 / 3.0).abs() < 1e-5);
+        // The confident false positive costs precision up to the 2/3 recall reached.
+        assert!((class_a.ap50 - (67.0 * 2.0 / 3.0) / 101.0).abs() < 1e-5);
+        assert_eq!(report.classes[1].ap50, 1.0);
+        assert_eq!(report.classes[1].ap50_95, 1.0);
+
+        assert_eq!(report.worst_images.len(), 1);
+        assert_eq!(report.worst_images[0].name, "disagrees");
+        assert_eq!(report.worst_images[0].false_positives, 1);
+        assert_eq!(report.worst_images[0].false_negatives, 1);
+    }
+}
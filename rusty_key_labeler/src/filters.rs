@@ -0,0 +1,285 @@
+use bevy::prelude::Resource;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use yolo_io::ImageLabelPair;
+
+use crate::review::ReviewStatus;
+
+/// Predicate deciding which image/label pairs are reachable by navigation.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageFilter {
+    #[default]
+    All,
+    Unlabeled,
+    Class {
+        id: isize,
+    },
+    BoxCount {
+        min: usize,
+        max: Option<usize>,
+    },
+    Review {
+        status: ReviewStatus,
+    },
+}
+
+impl ImageFilter {
+    /// `review_statuses` holds every image that is not unreviewed.
+    pub fn matches(
+        &self,
+        pair: &ImageLabelPair,
+        review_statuses: &HashMap<String, ReviewStatus>,
+    ) -> bool {
+        let entries = match &pair.label_file {
+            Some(label_file) => label_file.entries.as_slice(),
+            None => &[],
+        };
+
+        match self {
+            ImageFilter::All => true,
+            ImageFilter::Unlabeled => entries.is_empty(),
+            ImageFilter::Class { id } => entries.iter().any(|entry| entry.class == *id),
+            ImageFilter::BoxCount { min, max } => {
+                entries.len() >= *min && max.is_none_or(|max| entries.len() <= max)
+            }
+            ImageFilter::Review { status } => {
+                review_statuses.get(&pair.name).copied().unwrap_or_default() == *status
+            }
+        }
+    }
+
+    pub fn describe(&self, class_map: &HashMap<isize, String>) -> String {
+        match self {
+            ImageFilter::All => "all".to_string(),
+            ImageFilter::Unlabeled => "unlabeled".to_string(),
+            ImageFilter::Class { id } => match class_map.get(id) {
+                Some(name) => format!("class {}: {}", id, name),
+                None => format!("class {}", id),
+            },
+            ImageFilter::BoxCount {
+                min,
+                max: Some(max),
+            } => format!("{}-{} boxes", min, max),
+            ImageFilter::BoxCount { min, max: None } => format!("{}+ boxes", min),
+            ImageFilter::Review { status } => status.describe().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct NavigationFilter {
+    pub filter: ImageFilter,
+    /// Filters reachable with the `cycle_filter` key, in order.
+    pub cycle: Vec<ImageFilter>,
+    /// Indices into the project's valid pairs which match `filter`.
+    pub indices: Vec<usize>,
+    pub review_statuses: HashMap<String, ReviewStatus>,
+}
+
+impl NavigationFilter {
+    pub fn new(
+        filter: &ImageFilter,
+        class_map: &HashMap<isize, String>,
+        pairs: &[ImageLabelPair],
+    ) -> Self {
+        let mut cycle = vec![
+            ImageFilter::All,
+            ImageFilter::Unlabeled,
+            ImageFilter::Review {
+                status: ReviewStatus::Unreviewed,
+            },
+            ImageFilter::Review {
+                status: ReviewStatus::NeedsFix,
+            },
+        ];
+        let mut class_ids = class_map.keys().copied().collect::<Vec<isize>>();
+        class_ids.sort();
+        cycle.extend(class_ids.into_iter().map(|id| ImageFilter::Class { id }));
+
+        // A filter only reachable through config (e.g. box count) joins the cycle.
+        if !cycle.contains(filter) {
+            cycle.push(filter.clone());
+        }
+
+        let mut navigation_filter = Self {
+            filter: filter.clone(),
+            cycle,
+            indices: vec![],
+            review_statuses: HashMap::new(),
+        };
+        navigation_filter.rebuild(pairs);
+
+        navigation_filter
+    }
+
+    pub fn rebuild(&mut self, pairs: &[ImageLabelPair]) {
+        self.indices = pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, pair)| self.filter.matches(pair, &self.review_statuses))
+            .map(|(index, _)| index)
+            .collect();
+    }
+
+    pub fn set_review_statuses(
+        &mut self,
+        review_statuses: HashMap<String, ReviewStatus>,
+        pairs: &[ImageLabelPair],
+    ) {
+        self.review_statuses = review_statuses;
+        self.rebuild(pairs);
+    }
+
+    pub fn set_filter(&mut self, filter: ImageFilter, pairs: &[ImageLabelPair]) {
+        self.filter = filter;
+        self.rebuild(pairs);
+    }
+
+    pub fn cycle_next(&mut self, pairs: &[ImageLabelPair]) {
+        let position = self
+            .cycle
+            .iter()
+            .position(|filter| *filter == self.filter)
+            .map_or(0, |position| (position + 1) % self.cycle.len());
+
+        self.set_filter(self.cycle[position].clone(), pairs);
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.indices.binary_search(&index).is_ok()
+    }
+
+    /// Returns the pair index `step` matching images away from `current`,
+    /// wrapping around at both ends. When `current` itself does not match,
+    /// the first match in the direction of travel counts as one step.
+    pub fn step(&self, current: usize, step: isize) -> Option<usize> {
+        if self.indices.is_empty() {
+            return None;
+        }
+
+        // Reduced first, so huge steps can't overflow.
+        let len = self.indices.len() as isize;
+        let reduced = step.rem_euclid(len);
+        let position = match self.indices.binary_search(&current) {
+            Ok(position) => position as isize + reduced,
+            Err(insert_at) if step > 0 => insert_at as isize + reduced - 1,
+            Err(insert_at) => insert_at as isize + reduced,
+        };
+
+        Some(self.indices[position.rem_euclid(len) as usize])
+    }
+
+    /// The first matching index at or after `current`, wrapping around.
+    pub fn nearest(&self, current: usize) -> Option<usize> {
+        match self.contains(current) {
+            true => Some(current),
+            false => self.step(current, 1),
+        }
+    }
+
+    pub fn index_label(&self, current: usize, class_map: &HashMap<isize, String>) -> String {
+        let position = match self.indices.binary_search(&current) {
+            Ok(position) => (position + 1).to_string(),
+            Err(_) => "-".to_string(),
+        };
+
+        match self.filter {
+            ImageFilter::All => format!("{}/{}", position, self.indices.len()),
+            _ => format!(
+                "{}/{} [{}]",
+                position,
+                self.indices.len(),
+                self.filter.describe(class_map)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yolo_io::{YoloEntry, YoloFile};
+
+    use super::*;
+
+    fn pair_with_classes(name: &str, classes: &[isize]) -> ImageLabelPair {
+        ImageLabelPair {
+            name: name.to_string(),
+            image_path: None,
+            label_file: Some(YoloFile {
+                stem: name.to_string(),
+                path: format!("{}.txt", name),
+                entries: classes
+                    .iter()
+                    .map(|class| YoloEntry {
+                        class: *class,
+                        x_center: 0.5,
+                        y_center: 0.5,
+                        width: 0.1,
+                        height: 0.1,
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
+    fn test_pairs() -> Vec<ImageLabelPair> {
+        vec![
+            pair_with_classes("a", &[0]),
+            pair_with_classes("b", &[]),
+            pair_with_classes("c", &[1, 1]),
+            pair_with_classes("d", &[0, 1, 1]),
+        ]
+    }
+
+    #[test]
+    fn test_filter_indices() {
+        let pairs = test_pairs();
+        let class_map = HashMap::new();
+
+        let unlabeled = NavigationFilter::new(&ImageFilter::Unlabeled, &class_map, &pairs);
+        assert_eq!(unlabeled.indices, vec![1]);
+
+        let class = NavigationFilter::new(&ImageFilter::Class { id: 1 }, &class_map, &pairs);
+        assert_eq!(class.indices, vec![2, 3]);
+
+        let box_count = NavigationFilter::new(
+            &ImageFilter::BoxCount {
+                min: 2,
+                max: Some(2),
+            },
+            &class_map,
+            &pairs,
+        );
+        assert_eq!(box_count.indices, vec![2]);
+
+        let mut unreviewed = NavigationFilter::new(
+            &ImageFilter::Review {
+                status: ReviewStatus::Unreviewed,
+            },
+            &class_map,
+            &pairs,
+        );
+        unreviewed.set_review_statuses(
+            HashMap::from([("c".to_string(), ReviewStatus::Approved)]),
+            &pairs,
+        );
+        assert_eq!(unreviewed.indices, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_step_wraps_and_skips_non_matching() {
+        let pairs = test_pairs();
+        let filter = NavigationFilter::new(&ImageFilter::Class { id: 0 }, &HashMap::new(), &pairs);
+
+        assert_eq!(filter.step(0, 1), Some(3));
+        assert_eq!(filter.step(3, 1), Some(0));
+        assert_eq!(filter.step(0, -1), Some(3));
+        assert_eq!(filter.step(1, 1), Some(3));
+        assert_eq!(filter.step(1, -1), Some(0));
+        assert_eq!(filter.step(0, isize::MAX), Some(3));
+        assert_eq!(filter.step(1, isize::MIN), Some(3));
+        assert_eq!(filter.index_label(3, &HashMap::new()), "2/2 [class 0]");
+        assert_eq!(filter.index_label(1, &HashMap::new()), "-/2 [class 0]");
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    asset::{Handle, UntypedAssetId},
+    image::Image,
+    prelude::Resource,
+};
+use hashbrown::{HashMap, HashSet};
+use yolo_io::YoloFile;
+
+/// An image kept alive by the cache, together with its parsed labels.
+#[derive(Debug, Clone)]
+pub struct CachedImage {
+    pub handle: Handle<Image>,
+    pub label_file: Option<YoloFile>,
+    /// Decoded size of the image, zero until it has finished loading.
+    pub bytes: usize,
+    pub last_used: u64,
+}
+
+/// Holds strong handles to recently viewed and prefetched images so the
+/// asset server returns them already decoded when they are selected.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ImageCache {
+    pub entries: HashMap<PathBuf, CachedImage>,
+    pub budget_bytes: usize,
+    clock: u64,
+}
+
+impl ImageCache {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            ..Default::default()
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, handle: Handle<Image>, label_file: Option<YoloFile>) {
+        self.clock += 1;
+        self.entries.insert(
+            path,
+            CachedImage {
+                handle,
+                label_file,
+                bytes: 0,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    pub fn touch(&mut self, path: &Path) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.last_used = self.clock;
+        }
+    }
+
+    pub fn label_file_for(&self, image_id: UntypedAssetId) -> Option<&YoloFile> {
+        self.entries
+            .values()
+            .find(|entry| entry.handle.id().untyped() == image_id)
+            .and_then(|entry| entry.label_file.as_ref())
+    }
+
+    /// Path of the cached image with this asset id.
+    pub fn path_for(&self, image_id: UntypedAssetId) -> Option<&Path> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| entry.handle.id().untyped() == image_id)
+            .map(|(path, _)| path.as_path())
+    }
+
+    pub fn set_label_file(&mut self, path: &Path, label_file: Option<YoloFile>) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.label_file = label_file;
+        }
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.entries.values().map(|entry| entry.bytes).sum()
+    }
+
+    /// Drops least recently used entries until the cache fits its budget.
+    /// Entries in `keep` are never evicted, even when they alone exceed it.
+    pub fn evict(&mut self, keep: &HashSet<PathBuf>) -> Vec<PathBuf> {
+        let mut evicted = Vec::new();
+
+        while self.total_bytes() > self.budget_bytes {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(path, _)| !keep.contains(*path))
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone());
+
+            match oldest {
+                Some(path) => {
+                    self.entries.remove(&path);
+                    evicted.push(path);
+                }
+                None => break,
+            }
+        }
+
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_with_entries(budget_bytes: usize, paths: &[&str]) -> ImageCache {
+        let mut cache = ImageCache::new(budget_bytes);
+        for path in paths {
+            cache.insert(PathBuf::from(path), Handle::default(), None);
+            cache.entries.get_mut(Path::new(path)).unwrap().bytes = 100;
+        }
+        cache
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = cache_with_entries(200, &["a.png", "b.png", "c.png"]);
+        cache.touch(Path::new("a.png"));
+
+        let evicted = cache.evict(&HashSet::new());
+
+        assert_eq!(evicted, vec![PathBuf::from("b.png")]);
+        assert!(cache.contains(Path::new("a.png")));
+        assert!(cache.contains(Path::new("c.png")));
+    }
+
+    #[test]
+    fn test_never_evicts_kept_entries() {
+        let mut cache = cache_with_entries(0, &["a.png", "b.png"]);
+        let keep = HashSet::from([PathBuf::from("a.png")]);
+
+        let evicted = cache.evict(&keep);
+
+        assert_eq!(evicted, vec![PathBuf::from("b.png")]);
+        assert!(cache.contains(Path::new("a.png")));
+    }
+}
@@ -0,0 +1,532 @@
+use std::{fmt, str::FromStr};
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{ButtonInput, KeyCode, Res, Resource},
+};
+use serde::{de::value::StrDeserializer, Deserialize, Serialize};
+
+/// Seconds the first press of a chord waits for the second.
+pub const CHORD_TIMEOUT: f32 = 1.0;
+
+/// Modifier keys held with a key, either side of the keyboard.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    /// The modifiers held while `key` is pressed. A modifier key doesn't
+    /// count itself, so it can be bound on its own.
+    pub fn held(keys: &ButtonInput<KeyCode>, key: KeyCode) -> Self {
+        let held = |left, right| key != left && key != right && keys.any_pressed([left, right]);
+
+        Self {
+            ctrl: held(KeyCode::ControlLeft, KeyCode::ControlRight),
+            shift: held(KeyCode::ShiftLeft, KeyCode::ShiftRight),
+            alt: held(KeyCode::AltLeft, KeyCode::AltRight),
+        }
+    }
+
+    /// Whether every modifier in `self` is also in `other`.
+    fn within(&self, other: &Modifiers) -> bool {
+        (!self.ctrl || other.ctrl) && (!self.shift || other.shift) && (!self.alt || other.alt)
+    }
+
+    /// `self` with the modifiers in `other` released.
+    fn without(&self, other: &Modifiers) -> Self {
+        Self {
+            ctrl: self.ctrl && !other.ctrl,
+            shift: self.shift && !other.shift,
+            alt: self.alt && !other.alt,
+        }
+    }
+
+    fn union(&self, other: &Modifiers) -> Self {
+        Self {
+            ctrl: self.ctrl || other.ctrl,
+            shift: self.shift || other.shift,
+            alt: self.alt || other.alt,
+        }
+    }
+
+    /// The modifier `key` is, if any.
+    fn of_key(key: KeyCode) -> Self {
+        Self {
+            ctrl: matches!(key, KeyCode::ControlLeft | KeyCode::ControlRight),
+            shift: matches!(key, KeyCode::ShiftLeft | KeyCode::ShiftRight),
+            alt: matches!(key, KeyCode::AltLeft | KeyCode::AltRight),
+        }
+    }
+}
+
+fn is_modifier(key: KeyCode) -> bool {
+    matches!(
+        key,
+        KeyCode::ControlLeft
+            | KeyCode::ControlRight
+            | KeyCode::ShiftLeft
+            | KeyCode::ShiftRight
+            | KeyCode::AltLeft
+            | KeyCode::AltRight
+    )
+}
+
+/// One key with the modifiers held while pressing it, written `Ctrl+KeyS`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct KeyPress {
+    pub modifiers: Modifiers,
+    pub key: KeyCode,
+}
+
+impl KeyPress {
+    pub fn new(key: KeyCode) -> Self {
+        Self {
+            modifiers: Modifiers::default(),
+            key,
+        }
+    }
+
+    pub fn ctrl(mut self) -> Self {
+        self.modifiers.ctrl = true;
+        self
+    }
+
+    pub fn shift(mut self) -> Self {
+        self.modifiers.shift = true;
+        self
+    }
+
+    pub fn alt(mut self) -> Self {
+        self.modifiers.alt = true;
+        self
+    }
+
+    /// Pressed this frame with exactly these modifiers held.
+    pub fn just_pressed(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        self.just_pressed_ignoring(keys, Modifiers::default())
+    }
+
+    /// Like `just_pressed`, but `ignored` modifiers may be held or not, e.g.
+    /// a modifier held to change how the action behaves.
+    pub fn just_pressed_ignoring(&self, keys: &ButtonInput<KeyCode>, ignored: Modifiers) -> bool {
+        keys.just_pressed(self.key)
+            && Modifiers::held(keys, self.key).without(&ignored) == self.modifiers.without(&ignored)
+    }
+
+    /// Held with at least these modifiers, so held actions such as panning
+    /// keep going while another modifier is pressed.
+    pub fn pressed(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        keys.pressed(self.key) && self.modifiers.within(&Modifiers::held(keys, self.key))
+    }
+
+    pub fn just_released(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        keys.just_released(self.key) && Modifiers::held(keys, self.key) == self.modifiers
+    }
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.modifiers.ctrl, "Ctrl+"),
+            (self.modifiers.shift, "Shift+"),
+            (self.modifiers.alt, "Alt+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+
+        write!(f, "{:?}", self.key)
+    }
+}
+
+impl FromStr for KeyPress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('+').map(str::trim).collect::<Vec<_>>();
+        let key = parts.pop().unwrap_or_default();
+        let key = KeyCode::deserialize(StrDeserializer::<serde::de::value::Error>::new(key))
+            .map_err(|_| format!("Unknown key '{}' in '{}'", key, s))?;
+
+        let mut press = KeyPress::new(key);
+        for modifier in parts {
+            press = match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => press.ctrl(),
+                "shift" => press.shift(),
+                "alt" => press.alt(),
+                _ => return Err(format!("Unknown modifier '{}' in '{}'", modifier, s)),
+            };
+        }
+
+        Ok(press)
+    }
+}
+
+/// A key press, or a chord of two presses one after the other, written
+/// `KeyG KeyG`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KeyBinding(pub Vec<KeyPress>);
+
+impl KeyBinding {
+    pub fn is_chord(&self) -> bool {
+        self.0.len() == 2
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let presses = self.0.iter().map(KeyPress::to_string).collect::<Vec<_>>();
+        f.write_str(&presses.join(" "))
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let presses = s
+            .split_whitespace()
+            .map(KeyPress::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match presses.len() {
+            1 | 2 => Ok(KeyBinding(presses)),
+            _ => Err(format!("'{}' should be one key or a chord of two keys", s)),
+        }
+    }
+}
+
+/// Every binding of one action, written as one binding or a list of them.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct KeyBindings(pub Vec<KeyBinding>);
+
+impl From<KeyCode> for KeyBindings {
+    fn from(key: KeyCode) -> Self {
+        KeyPress::new(key).into()
+    }
+}
+
+impl<const N: usize> From<[KeyCode; N]> for KeyBindings {
+    fn from(keys: [KeyCode; N]) -> Self {
+        KeyBindings(
+            keys.into_iter()
+                .map(|key| KeyBinding(vec![KeyPress::new(key)]))
+                .collect(),
+        )
+    }
+}
+
+impl From<KeyPress> for KeyBindings {
+    fn from(press: KeyPress) -> Self {
+        KeyBindings(vec![KeyBinding(vec![press])])
+    }
+}
+
+impl KeyBindings {
+    /// Modifiers held while any of these bindings is held, counting bound
+    /// modifier keys such as `ShiftLeft`.
+    pub fn modifiers(&self) -> Modifiers {
+        self.0
+            .iter()
+            .filter(|binding| !binding.is_chord())
+            .map(|binding| binding.0[0])
+            .fold(Modifiers::default(), |modifiers, press| {
+                modifiers
+                    .union(&press.modifiers)
+                    .union(&Modifiers::of_key(press.key))
+            })
+    }
+}
+
+impl fmt::Display for KeyBindings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bindings = self.0.iter().map(KeyBinding::to_string).collect::<Vec<_>>();
+        f.write_str(&bindings.join(", "))
+    }
+}
+
+impl Serialize for KeyBindings {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let bindings = self.0.iter().map(KeyBinding::to_string).collect::<Vec<_>>();
+
+        match bindings.as_slice() {
+            [binding] => serializer.serialize_str(binding),
+            _ => bindings.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBindings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Debug, Deserialize)]
+        #[serde(untagged)]
+        enum KeyBindingsHelper {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        let bindings = match KeyBindingsHelper::deserialize(deserializer)? {
+            KeyBindingsHelper::One(binding) => vec![binding],
+            KeyBindingsHelper::Many(bindings) => bindings,
+        };
+
+        bindings
+            .iter()
+            .map(|binding| binding.parse())
+            .collect::<Result<Vec<KeyBinding>, String>>()
+            .map(KeyBindings)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Describes bindings shared by two actions, and single keys that also
+/// start a chord, which would fire before the chord could finish.
+pub fn find_conflicts(actions: &[(&str, &KeyBindings)]) -> Vec<String> {
+    let bindings = actions
+        .iter()
+        .flat_map(|(action, bindings)| bindings.0.iter().map(move |binding| (*action, binding)))
+        .collect::<Vec<_>>();
+
+    let mut conflicts = vec![];
+    for (index, (action, binding)) in bindings.iter().enumerate() {
+        for (other_action, other) in bindings[index + 1..].iter() {
+            if binding == other {
+                conflicts.push(format!(
+                    "{} is bound to both {} and {}",
+                    binding, action, other_action
+                ));
+                continue;
+            }
+
+            let prefix = match (binding.is_chord(), other.is_chord()) {
+                (false, true) if other.0[0] == binding.0[0] => {
+                    Some((binding, action, other, other_action))
+                }
+                (true, false) if binding.0[0] == other.0[0] => {
+                    Some((other, other_action, binding, action))
+                }
+                _ => None,
+            };
+            if let Some((single, single_action, chord, chord_action)) = prefix {
+                conflicts.push(format!(
+                    "{} ({}) starts the chord {} ({})",
+                    single, single_action, chord, chord_action
+                ));
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// Progress through two-key chords, updated once a frame before `Update`.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct KeyChords {
+    /// First press of a chord and when it was pressed.
+    pending: Option<(KeyPress, f32)>,
+    /// First press of the chord finished by this frame's press.
+    completed: Option<KeyPress>,
+}
+
+impl KeyChords {
+    pub fn update(
+        &mut self,
+        keys: &ButtonInput<KeyCode>,
+        actions: &[(&str, &KeyBindings)],
+        now: f32,
+    ) {
+        self.completed = None;
+
+        let press = keys
+            .get_just_pressed()
+            .find(|key| !is_modifier(**key))
+            .map(|key| KeyPress {
+                modifiers: Modifiers::held(keys, *key),
+                key: *key,
+            });
+
+        if self
+            .pending
+            .is_some_and(|(_, pressed_at)| now - pressed_at > CHORD_TIMEOUT)
+        {
+            self.pending = None;
+        }
+
+        let press = match press {
+            Some(press) => press,
+            None => return,
+        };
+
+        let mut chords = actions
+            .iter()
+            .flat_map(|(_, bindings)| bindings.0.iter())
+            .filter(|binding| binding.is_chord());
+
+        if let Some((first, _)) = self.pending.take() {
+            if chords
+                .clone()
+                .any(|chord| chord.0[0] == first && chord.0[1] == press)
+            {
+                self.completed = Some(first);
+                return;
+            }
+        }
+
+        if chords.any(|chord| chord.0[0] == press) {
+            self.pending = Some((press, now));
+        }
+    }
+}
+
+/// Keyboard state read through key bindings, in place of
+/// `ButtonInput<KeyCode>`.
+#[derive(SystemParam)]
+pub struct KeyInput<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    chords: Res<'w, KeyChords>,
+}
+
+impl KeyInput<'_> {
+    /// A single press matched this frame, or the second press of a chord.
+    /// The second press of a chord doesn't also trigger its single binding.
+    pub fn just_pressed(&self, bindings: &KeyBindings) -> bool {
+        self.just_pressed_ignoring(bindings, &KeyBindings::default())
+    }
+
+    /// Like `just_pressed`, while the modifiers of `held_with` may also be
+    /// held, e.g. `disable_snapping` while placing a corner.
+    pub fn just_pressed_ignoring(&self, bindings: &KeyBindings, held_with: &KeyBindings) -> bool {
+        let ignored = held_with.modifiers();
+
+        bindings.0.iter().any(|binding| match binding.0.as_slice() {
+            [press] => {
+                self.chords.completed.is_none() && press.just_pressed_ignoring(&self.keys, ignored)
+            }
+            [first, second] => {
+                self.chords.completed == Some(*first)
+                    && second.just_pressed_ignoring(&self.keys, ignored)
+            }
+            _ => false,
+        })
+    }
+
+    /// Held down. Chords only trigger on press.
+    pub fn pressed(&self, bindings: &KeyBindings) -> bool {
+        bindings.0.iter().any(|binding| match binding.0.as_slice() {
+            [press] => press.pressed(&self.keys),
+            _ => false,
+        })
+    }
+
+    pub fn just_released(&self, bindings: &KeyBindings) -> bool {
+        bindings.0.iter().any(|binding| match binding.0.as_slice() {
+            [press] => press.just_released(&self.keys),
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::KeyMap;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_bindings() {
+        let bindings: KeyBindings =
+            serde_yml::from_str("['Ctrl+Shift+KeyS', 'KeyG KeyG', 'Delete']").unwrap();
+
+        assert_eq!(
+            bindings,
+            KeyBindings(vec![
+                KeyBinding(vec![KeyPress::new(KeyCode::KeyS).ctrl().shift()]),
+                KeyBinding(vec![
+                    KeyPress::new(KeyCode::KeyG),
+                    KeyPress::new(KeyCode::KeyG)
+                ]),
+                KeyBinding(vec![KeyPress::new(KeyCode::Delete)]),
+            ])
+        );
+        assert_eq!(bindings.to_string(), "Ctrl+Shift+KeyS, KeyG KeyG, Delete");
+        assert_eq!(
+            serde_yml::from_str::<KeyBindings>("'KeyE'").unwrap(),
+            KeyBindings::from(KeyCode::KeyE)
+        );
+        assert!(serde_yml::from_str::<KeyBindings>("'Hyper+KeyE'").is_err());
+        assert!(serde_yml::from_str::<KeyBindings>("'KeyA KeyB KeyC'").is_err());
+    }
+
+    #[test]
+    fn test_default_key_map_has_no_conflicts() {
+        assert_eq!(KeyMap::default().conflicts(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_draw_box_ignores_disable_snapping() {
+        let key_map = KeyMap::default();
+        let draw_box = key_map.draw_box.0[0].0[0];
+        let mut keys = ButtonInput::<KeyCode>::default();
+
+        keys.press(KeyCode::ShiftLeft);
+        keys.press(KeyCode::KeyB);
+
+        assert!(!draw_box.just_pressed(&keys));
+        assert!(draw_box.just_pressed_ignoring(&keys, key_map.disable_snapping.modifiers()));
+
+        keys.press(KeyCode::ControlLeft);
+        assert!(!draw_box.just_pressed_ignoring(&keys, key_map.disable_snapping.modifiers()));
+    }
+
+    #[test]
+    fn test_chords_and_conflicts() {
+        let save = "KeyG KeyS".parse::<KeyBinding>().unwrap();
+        let grid = KeyBindings::from(KeyCode::KeyG);
+        let stats = KeyBindings::from(KeyCode::KeyS);
+        let chord = KeyBindings(vec![save]);
+
+        assert_eq!(
+            find_conflicts(&[("grid", &grid), ("stats", &stats), ("save", &chord)]),
+            vec!["KeyG (grid) starts the chord KeyG KeyS (save)".to_string()]
+        );
+        assert_eq!(
+            find_conflicts(&[("grid", &grid), ("crosshair", &grid)]),
+            vec!["KeyG is bound to both grid and crosshair".to_string()]
+        );
+
+        let actions = [("stats", &stats), ("save", &chord)];
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mut chords = KeyChords::default();
+
+        keys.press(KeyCode::KeyG);
+        chords.update(&keys, &actions, 0.0);
+        assert!(chords.pending.is_some());
+
+        keys.clear();
+        keys.release(KeyCode::KeyG);
+        keys.press(KeyCode::KeyS);
+        chords.update(&keys, &actions, 0.5);
+        assert_eq!(chords.completed, Some(KeyPress::new(KeyCode::KeyG)));
+
+        // Too slow, so the second key acts on its own.
+        keys.clear();
+        keys.release(KeyCode::KeyS);
+        keys.press(KeyCode::KeyG);
+        chords.update(&keys, &actions, 1.0);
+        keys.clear();
+        keys.release(KeyCode::KeyG);
+        keys.press(KeyCode::KeyS);
+        chords.update(&keys, &actions, 2.5);
+        assert_eq!(chords.completed, None);
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use bevy::math::{Rect, Vec2};
+use yolo_io::{YoloEntry, YoloFile};
+
+/// An entry's box in image pixels, with y pointing down.
+pub fn entry_pixel_rect(entry: &YoloEntry, image_size: Vec2) -> Rect {
+    Rect::from_center_size(
+        Vec2::new(entry.x_center, entry.y_center) * image_size,
+        Vec2::new(entry.width, entry.height) * image_size,
+    )
+}
+
+pub fn pixel_rect_to_entry(class: isize, rect: Rect, image_size: Vec2) -> YoloEntry {
+    let rect = rect.intersect(Rect::from_corners(Vec2::ZERO, image_size));
+    let center = rect.center() / image_size;
+    let size = rect.size() / image_size;
+
+    YoloEntry {
+        class,
+        x_center: center.x,
+        y_center: center.y,
+        width: size.x,
+        height: size.y,
+    }
+}
+
+pub fn format_label_entries(entries: &[YoloEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{} {:.6} {:.6} {:.6} {:.6}\n",
+                entry.class, entry.x_center, entry.y_center, entry.width, entry.height
+            )
+        })
+        .collect()
+}
+
+pub fn write_label_file(label_file: &YoloFile) -> std::io::Result<()> {
+    if let Some(parent) = Path::new(&label_file.path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(&label_file.path, format_label_entries(&label_file.entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_rect_to_entry() {
+        let image_size = Vec2::new(200.0, 100.0);
+        let rect = Rect::new(20.0, 10.0, 60.0, 50.0);
+
+        let entry = pixel_rect_to_entry(1, rect, image_size);
+        assert_eq!(entry.x_center, 0.2);
+        assert_eq!(entry.height, 0.4);
+        assert_eq!(entry.width, 0.2);
+        assert_eq!(
+            format_label_entries(&[entry]),
+            "1 0.200000 0.300000 0.200000 0.400000\n"
+        );
+    }
+}
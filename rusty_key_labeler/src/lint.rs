@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{math::Vec2, prelude::Resource};
+use hashbrown::HashMap;
+use yolo_io::{PairingError, PairingResult, YoloEntry, YoloFileParseError};
+
+use crate::{comparison::entry_iou, settings::LintSettings};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LintKind {
+    /// Narrower or shorter than `min_size` image pixels.
+    TooSmall {
+        width: f32,
+        height: f32,
+    },
+    OutOfBounds,
+    /// Overlaps an earlier box of the same class above `duplicate_iou`.
+    NearDuplicate {
+        other: usize,
+        iou: f32,
+    },
+    ZeroArea,
+    UnknownClass(isize),
+    /// The label file was rejected when the project was loaded.
+    Unparsable(String),
+}
+
+impl LintKind {
+    pub fn describe(&self) -> String {
+        match self {
+            LintKind::TooSmall { width, height } => {
+                format!("box is only {:.1}x{:.1} px", width, height)
+            }
+            LintKind::OutOfBounds => "box extends past the image".to_string(),
+            LintKind::NearDuplicate { other, iou } => {
+                format!("box duplicates box {} (IoU {:.2})", other, iou)
+            }
+            LintKind::ZeroArea => "box has zero area".to_string(),
+            LintKind::UnknownClass(class) => format!("unknown class {}", class),
+            LintKind::Unparsable(e) => e.clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LintIssue {
+    /// Index among the project's valid pairs, when the image can be opened.
+    pub pair_index: Option<usize>,
+    pub path: PathBuf,
+    /// Index of the offending entry in the label file.
+    pub entry: Option<usize>,
+    pub kind: LintKind,
+}
+
+impl LintIssue {
+    pub fn describe(&self) -> String {
+        match (self.entry, &self.kind) {
+            (Some(entry), kind) => format!("{:?} #{}: {}", self.path, entry, kind.describe()),
+            // Parse errors already name their file.
+            (None, LintKind::Unparsable(e)) => e.clone(),
+            (None, kind) => format!("{:?}: {}", self.path, kind.describe()),
+        }
+    }
+}
+
+/// Checks one label file's entries. Size checks are skipped when the image
+/// size is unknown.
+pub fn lint_entries(
+    entries: &[YoloEntry],
+    image_size: Option<Vec2>,
+    class_map: &HashMap<isize, String>,
+    settings: &LintSettings,
+) -> Vec<(usize, LintKind)> {
+    let mut issues = vec![];
+
+    for (index, entry) in entries.iter().enumerate() {
+        if !class_map.contains_key(&entry.class) {
+            issues.push((index, LintKind::UnknownClass(entry.class)));
+        }
+
+        if entry.width <= 0.0 || entry.height <= 0.0 {
+            issues.push((index, LintKind::ZeroArea));
+            continue;
+        }
+
+        if let Some(image_size) = image_size {
+            let (width, height) = (entry.width * image_size.x, entry.height * image_size.y);
+            if width < settings.min_size || height < settings.min_size {
+                issues.push((index, LintKind::TooSmall { width, height }));
+            }
+        }
+
+        let out_of_bounds = entry.x_center - entry.width / 2.0 < 0.0
+            || entry.x_center + entry.width / 2.0 > 1.0
+            || entry.y_center - entry.height / 2.0 < 0.0
+            || entry.y_center + entry.height / 2.0 > 1.0;
+        if out_of_bounds {
+            issues.push((index, LintKind::OutOfBounds));
+        }
+
+        let duplicate = entries[..index]
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| other.class == entry.class)
+            .map(|(other, other_entry)| (other, entry_iou(entry, other_entry)))
+            .find(|(_, iou)| *iou > settings.duplicate_iou);
+        if let Some((other, iou)) = duplicate {
+            issues.push((index, LintKind::NearDuplicate { other, iou }));
+        }
+    }
+
+    issues
+}
+
+/// Lints every label file in the project, in project order.
+pub fn lint_project(
+    results: &[PairingResult],
+    class_map: &HashMap<isize, String>,
+    settings: &LintSettings,
+    mut image_size: impl FnMut(&Path) -> Option<Vec2>,
+) -> Vec<LintIssue> {
+    let mut issues = vec![];
+    let mut pair_index = 0;
+
+    for result in results {
+        let pair = match result {
+            PairingResult::Valid(pair) => pair,
+            PairingResult::Invalid(PairingError::LabelFileError(e)) => {
+                let (path, kind) = match e {
+                    YoloFileParseError::EmptyFile(_) => continue,
+                    YoloFileParseError::ClassIdNotFound(details) => (
+                        &details.path,
+                        LintKind::UnknownClass(
+                            details
+                                .class
+                                .as_ref()
+                                .and_then(|class| class.parse().ok())
+                                .unwrap_or(-1),
+                        ),
+                    ),
+                    YoloFileParseError::InvalidFormat(details)
+                    | YoloFileParseError::DuplicateEntries(details)
+                    | YoloFileParseError::FailedToParseClassId(details)
+                    | YoloFileParseError::LabelDataOutOfRange(details)
+                    | YoloFileParseError::FailedToParseColumn(details)
+                    | YoloFileParseError::FailedToGetFileStem(details) => {
+                        (&details.path, LintKind::Unparsable(e.to_string()))
+                    }
+                };
+
+                let issue = LintIssue {
+                    pair_index: None,
+                    path: PathBuf::from(path),
+                    entry: None,
+                    kind,
+                };
+
+                // A rejected label file can be reported by more than one pairing.
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+                continue;
+            }
+            PairingResult::Invalid(_) => continue,
+        };
+
+        let entries = pair
+            .label_file
+            .as_ref()
+            .map_or(&[][..], |label_file| label_file.entries.as_slice());
+        let size = match entries.is_empty() {
+            true => None,
+            false => pair.image_path.as_deref().and_then(&mut image_size),
+        };
+
+        for (entry, kind) in lint_entries(entries, size, class_map, settings) {
+            issues.push(LintIssue {
+                pair_index: Some(pair_index),
+                path: pair.image_path.clone().unwrap_or_default(),
+                entry: Some(entry),
+                kind,
+            });
+        }
+
+        pair_index += 1;
+    }
+
+    issues
+}
+
+/// Where the next issue key last took the user.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct LintWalk {
+    pub last: Option<(usize, usize)>,
+}
+
+impl LintWalk {
+    /// The first openable issue after the last one visited, wrapping around.
+    /// Issues must be in project order.
+    pub fn next<'a>(&self, issues: &'a [LintIssue]) -> Option<&'a LintIssue> {
+        let mut openable = issues
+            .iter()
+            .filter(|issue| issue.pair_index.is_some() && issue.entry.is_some());
+        let first = openable.clone().next();
+
+        match self.last {
+            Some(last) => openable
+                .find(|issue| (issue.pair_index.unwrap(), issue.entry.unwrap()) > last)
+                .or(first),
+            None => first,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(class: isize, x_center: f32, y_center: f32, width: f32, height: f32) -> YoloEntry {
+        YoloEntry {
+            class,
+            x_center,
+            y_center,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_lints_each_rule() {
+        let entries = vec![
+            entry(0, 0.5, 0.5, 0.2, 0.2),
+            entry(0, 0.51, 0.5, 0.2, 0.2),
+            entry(1, 0.51, 0.5, 0.2, 0.2),
+            entry(0, 0.05, 0.5, 0.2, 0.2),
+            entry(0, 0.2, 0.2, 0.01, 0.2),
+            entry(0, 0.8, 0.8, 0.0, 0.1),
+            entry(7, 0.8, 0.2, 0.1, 0.1),
+        ];
+        let class_map = HashMap::from([(0, "a".to_string()), (1, "b".to_string())]);
+
+        let issues = lint_entries(
+            &entries,
+            Some(Vec2::new(100.0, 100.0)),
+            &class_map,
+            &LintSettings::default(),
+        );
+
+        assert_eq!(
+            issues
+                .iter()
+                .map(|(index, kind)| (*index, std::mem::discriminant(kind)))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    1,
+                    std::mem::discriminant(&LintKind::NearDuplicate { other: 0, iou: 0.0 })
+                ),
+                (3, std::mem::discriminant(&LintKind::OutOfBounds)),
+                (
+                    4,
+                    std::mem::discriminant(&LintKind::TooSmall {
+                        width: 0.0,
+                        height: 0.0
+                    })
+                ),
+                (5, std::mem::discriminant(&LintKind::ZeroArea)),
+                (6, std::mem::discriminant(&LintKind::UnknownClass(7))),
+            ]
+        );
+    }
+}
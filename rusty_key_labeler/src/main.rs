@@ -1,8 +1,28 @@
 mod bounding_boxes;
+mod cli;
+mod comparison;
 mod components;
+mod config_watch;
+mod detector;
+mod evaluation;
+mod filters;
+mod image_cache;
+mod key_bindings;
+mod labels;
+mod lint;
+mod modal;
+mod predictions;
 mod resources;
+mod review;
+mod session;
 mod settings;
+mod shrink;
+mod snapping;
+mod sprite_grid;
+mod stats;
+mod suggestions;
 mod systems;
+mod thumbnails;
 mod utils;
 
 use bevy::prelude::*;
@@ -10,9 +30,26 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_ui_views::BevyUiViewsPlugin;
 use bevy_vector_shapes::Shape2dPlugin;
 use bounding_boxes::BoundingBoxPainter;
+use cli::{Command, USAGE};
+use comparison::{DisagreementRanking, ImageComparison};
 use components::*;
+use config_watch::{read_config, ConfigWatch};
+use detector::DetectorTasks;
+use evaluation::write_evaluation_report;
+use filters::NavigationFilter;
+use image_cache::ImageCache;
+use key_bindings::KeyChords;
+use lint::{lint_project, LintWalk};
+use modal::ModalState;
+use predictions::Predictions;
 use resources::*;
+use review::ReviewManifest;
+use session::SessionState;
+use sprite_grid::SpriteGridPreview;
+use stats::{read_image_size, write_stats_report};
+use suggestions::{BoxProposals, SuggestionTasks};
 use systems::*;
+use thumbnails::ThumbnailCache;
 use yolo_io::YoloProject;
 
 struct AppInputs {
@@ -21,13 +58,35 @@ struct AppInputs {
     pub bounding_box_painter: BoundingBoxPainter,
     pub ui: Ui,
     pub app_data: AppData,
+    pub navigation_filter: NavigationFilter,
+    pub session: Option<SessionState>,
+    pub review_manifest: ReviewManifest,
+    pub start_image: Option<StartImage>,
+    pub image_cache: ImageCache,
+    pub thumbnail_cache: ThumbnailCache,
+    pub startup_warnings: StartupWarnings,
 }
 
-fn prepare_app_inputs(path: &str) -> Result<AppInputs, Box<dyn std::error::Error>> {
-    let data = std::fs::read_to_string(path)?;
-    let config: Config = serde_yml::from_str(&data)?;
+const CONFIG_PATH: &str = "rusty_key_labeler/config.yaml";
+
+fn prepare_app_inputs(
+    path: &str,
+    open: Option<std::path::PathBuf>,
+) -> Result<AppInputs, Box<dyn std::error::Error>> {
+    let (config, data) = read_config(path)?;
     let project = YoloProject::new(&config.project_config)?;
 
+    let conflicts = [
+        config.settings.key_map.conflicts(),
+        config.settings.modal.conflicts(&config.settings.key_map),
+    ];
+    let mut startup_warnings = StartupWarnings::default();
+    for conflict in conflicts.iter().flatten() {
+        startup_warnings
+            .0
+            .push(format!("Key binding conflict: {}", conflict));
+    }
+
     let bb_painter = BoundingBoxPainter::new(
         &config.settings.bounding_boxes,
         &config.project_config.export.class_map,
@@ -39,12 +98,46 @@ fn prepare_app_inputs(path: &str) -> Result<AppInputs, Box<dyn std::error::Error
         &config.settings.ui_panel.font_path,
     );
 
+    // A missing or unreadable session simply starts from the beginning.
+    let session = SessionState::load(&config.output_path).unwrap_or_else(|e| {
+        startup_warnings
+            .0
+            .push(format!("Ignoring unreadable session state: {}", e));
+        None
+    });
+
+    let review_manifest = ReviewManifest::load(&config.output_path).unwrap_or_else(|e| {
+        startup_warnings
+            .0
+            .push(format!("Ignoring unreadable review manifest: {}", e));
+        ReviewManifest::default()
+    });
+
+    let mut navigation_filter = NavigationFilter::new(
+        session
+            .as_ref()
+            .map_or(&config.settings.navigation_filter, |session| {
+                &session.filter
+            }),
+        &config.project_config.export.class_map,
+        &project.get_valid_pairs(),
+    );
+    navigation_filter.set_review_statuses(review_manifest.statuses(), &project.get_valid_pairs());
+
+    let image_cache = ImageCache::new(config.settings.prefetch.memory_budget_mb * 1024 * 1024);
+
+    let thumbnail_cache = ThumbnailCache::new(
+        &config.output_path,
+        config.settings.filmstrip.thumbnail_size,
+    );
+
     let app_data = AppData {
         index: 0,
         ui_eid: None,
         yolo_project: project,
         config: config.clone(),
         left_panel_eid: None,
+        active_class: session.as_ref().and_then(|session| session.active_class),
     };
 
     Ok(AppInputs {
@@ -53,6 +146,13 @@ fn prepare_app_inputs(path: &str) -> Result<AppInputs, Box<dyn std::error::Error
         bounding_box_painter: bb_painter,
         ui,
         app_data,
+        navigation_filter,
+        session,
+        review_manifest,
+        start_image: open.map(StartImage),
+        image_cache,
+        thumbnail_cache,
+        startup_warnings,
     })
 }
 
@@ -60,10 +160,30 @@ fn main() {
     // Load YAML configuration file from file.
     // https://github.com/sebastienrousseau/serde_yml
 
-    match prepare_app_inputs("rusty_key_labeler/config.yaml") {
+    let open = match Command::parse(std::env::args().skip(1)) {
+        Ok(Command::Label { open }) => open,
+        Ok(Command::Evaluate { output }) => std::process::exit(evaluate(output)),
+        Ok(Command::Stats { output }) => std::process::exit(stats(output)),
+        Ok(Command::Lint) => std::process::exit(lint()),
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match prepare_app_inputs(CONFIG_PATH, open) {
         Ok(app_inputs) => {
-            App::new()
-                .init_resource::<Assets<ColorMaterial>>()
+            let mut app = App::new();
+
+            if let Some(session) = app_inputs.session {
+                app.insert_resource(session);
+            }
+
+            if let Some(start_image) = app_inputs.start_image {
+                app.insert_resource(start_image);
+            }
+
+            app.init_resource::<Assets<ColorMaterial>>()
                 .add_plugins((
                     DefaultPlugins.set(ImagePlugin::default_nearest()), // Makes images crisp
                     WorldInspectorPlugin::new(),
@@ -73,27 +193,140 @@ fn main() {
                 .insert_resource(app_inputs.bounding_box_painter)
                 .insert_resource(app_inputs.app_data)
                 .insert_resource(app_inputs.ui)
-                .add_systems(Startup, (setup,))
+                .insert_resource(app_inputs.navigation_filter)
+                .insert_resource(app_inputs.image_cache)
+                .insert_resource(app_inputs.thumbnail_cache)
+                .insert_resource(app_inputs.review_manifest)
+                .insert_resource(app_inputs.startup_warnings)
+                .insert_resource(ConfigWatch::new(CONFIG_PATH))
+                .init_resource::<NavigationRepeat>()
+                .init_resource::<GuideOverlays>()
+                .init_resource::<BoxDrawing>()
+                .init_resource::<BoxProposals>()
+                .init_resource::<SuggestionTasks>()
+                .init_resource::<DetectorTasks>()
+                .init_resource::<ImageComparison>()
+                .init_resource::<DisagreementRanking>()
+                .init_resource::<LintWalk>()
+                .init_resource::<TextEntry>()
+                .init_resource::<KeyChords>()
+                .init_resource::<ModalState>()
+                .init_resource::<PendingBoxFocus>()
+                .insert_resource(Predictions::new(
+                    app_inputs.config.settings.predictions.confidence_threshold,
+                ))
+                .init_resource::<SpriteGridPreview>()
+                .add_systems(Startup, (log_startup_warnings, setup))
+                .add_systems(
+                    PreUpdate,
+                    (capture_text_entry, handle_modal_keys, update_key_chords)
+                        .chain()
+                        .after(bevy::input::InputSystem),
+                )
                 .add_systems(
                     Update,
                     (
-                        image_selection_system,
-                        load_bounding_boxes,
-                        update_labeling_index,
-                        update_current_file_name_label,
-                        debounce_timer_system,
-                        image_state_system,
-                        translate_image_system,
-                        zoom_image_system,
-                        fit_to_viewport,
-                        center_in_viewport,
-                        compute_viewport,
-                        cycle_bounding_box_selection,
-                        highlight_bounding_box,
-                        select_bounding_box_nearest_center,
+                        (
+                            reload_config,
+                            quit_app,
+                            image_selection_system,
+                            cycle_navigation_filter,
+                            prefetch_neighbouring_images,
+                            generate_thumbnails,
+                            load_bounding_boxes,
+                        )
+                            .chain(),
+                        (
+                            apply_modal_actions,
+                            extend_visual_selection,
+                            draw_visual_selection,
+                            update_mode_indicator,
+                        )
+                            .chain(),
+                        (
+                            review_image,
+                            review_selected_box,
+                            apply_review_comment,
+                            next_flagged_box,
+                            update_review_badge,
+                            update_text_entry_prompt,
+                        )
+                            .chain(),
+                        (
+                            update_labeling_index,
+                            update_current_file_name_label,
+                            update_scrub_preview,
+                            update_filmstrip,
+                            rebuild_minimap,
+                            update_minimap_viewport,
+                            toggle_minimap,
+                            update_cursor_readout,
+                            toggle_stats_panel,
+                            update_stats_panel,
+                            toggle_help_overlay,
+                            update_help_overlay,
+                        )
+                            .chain(),
+                        (
+                            image_state_system,
+                            translate_image_system,
+                            zoom_image_system,
+                            zoom_preset_system,
+                            fit_to_viewport,
+                            center_in_viewport,
+                            compute_viewport,
+                        )
+                            .chain(),
+                        (
+                            cycle_bounding_box_selection.run_if(no_pending_proposals),
+                            highlight_bounding_box,
+                            select_bounding_box_nearest_center.run_if(no_pending_proposals),
+                            toggle_guides,
+                            draw_guides,
+                        )
+                            .chain(),
+                        (
+                            (
+                                cancel_box_drawing,
+                                draw_bounding_box.run_if(box_drawing_enabled),
+                            )
+                                .chain(),
+                            move_bounding_box_corner.run_if(box_drawing_enabled),
+                            delete_bounding_box,
+                            draw_box_preview.run_if(box_drawing_enabled),
+                            suggest_boxes,
+                            receive_suggestions,
+                            request_detections,
+                            receive_detections,
+                            review_box_proposals,
+                            accept_confident_proposals,
+                            draw_box_proposals,
+                            toggle_sprite_grid_preview,
+                            label_sprite_grid,
+                            draw_sprite_grid,
+                            shrink_bounding_boxes,
+                            (
+                                load_predictions,
+                                adjust_confidence_threshold,
+                                accept_predictions,
+                                draw_predictions,
+                                compare_labels.run_if(comparison_inputs_changed),
+                                draw_comparison,
+                                update_comparison_summary,
+                                jump_to_disagreement,
+                            )
+                                .chain(),
+                            next_lint_issue,
+                            focus_pending_box,
+                            draw_box_flags,
+                            apply_label_edits,
+                        )
+                            .chain(),
+                        (restore_session_camera, save_session_state).chain(),
                     )
                         .chain(),
                 )
+                .add_systems(Last, save_session_on_exit)
                 .run();
         }
         Err(e) => {
@@ -101,3 +334,84 @@ fn main() {
         }
     }
 }
+
+/// Writes the evaluation report and returns the process exit code.
+fn evaluate(output: Option<std::path::PathBuf>) -> i32 {
+    let config = match read_config(CONFIG_PATH) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            eprintln!("Unable to read {}: {}", CONFIG_PATH, e);
+            return 1;
+        }
+    };
+
+    let output_dir = output.unwrap_or_else(|| config.output_path.clone().into());
+    match write_evaluation_report(&config, &output_dir) {
+        Ok(report) => {
+            println!("{}", report.to_markdown());
+            println!("Report written to {}", output_dir.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Unable to evaluate predictions: {}", e);
+            1
+        }
+    }
+}
+
+/// Writes the dataset statistics and returns the process exit code.
+fn stats(output: Option<std::path::PathBuf>) -> i32 {
+    let config = match read_config(CONFIG_PATH) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            eprintln!("Unable to read {}: {}", CONFIG_PATH, e);
+            return 1;
+        }
+    };
+
+    let output_dir = output.unwrap_or_else(|| config.output_path.clone().into());
+    match write_stats_report(&config, &output_dir) {
+        Ok(stats) => {
+            println!("{}", stats.to_markdown());
+            println!("Statistics written to {}", output_dir.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Unable to compute statistics: {}", e);
+            1
+        }
+    }
+}
+
+/// Prints every lint issue and returns the process exit code.
+fn lint() -> i32 {
+    let config = match read_config(CONFIG_PATH) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            eprintln!("Unable to read {}: {}", CONFIG_PATH, e);
+            return 1;
+        }
+    };
+
+    let project = match YoloProject::new(&config.project_config) {
+        Ok(project) => project,
+        Err(e) => {
+            eprintln!("Unable to load the project: {}", e);
+            return 1;
+        }
+    };
+
+    let issues = lint_project(
+        &project.data.pairs,
+        &config.project_config.export.class_map,
+        &config.settings.lint,
+        read_image_size,
+    );
+
+    for issue in issues.iter() {
+        println!("{}", issue.describe());
+    }
+    println!("{} issues", issues.len());
+
+    0
+}
@@ -0,0 +1,203 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use bevy::prelude::{KeyCode, Resource};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum InputMode {
+    /// Navigation and selection; box drawing keys are off.
+    #[default]
+    Normal,
+    /// Box drawing keys are on.
+    Insert,
+    /// Boxes selected while in this mode are collected for deleting or
+    /// relabelling together.
+    Visual,
+}
+
+impl InputMode {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            InputMode::Normal => "NORMAL",
+            InputMode::Insert => "INSERT",
+            InputMode::Visual => "VISUAL",
+        }
+    }
+}
+
+/// Largest count that can be typed; further digits are ignored.
+pub const MAX_COUNT: usize = 99_999;
+
+/// The entries a counted command starting at `first` covers, within the
+/// `entries` an image has.
+pub fn counted_entries(first: usize, count: usize, entries: usize) -> Range<usize> {
+    first.min(entries)..first.saturating_add(count).min(entries)
+}
+
+/// The digit `key` types into a count.
+pub fn count_digit(key: KeyCode) -> Option<usize> {
+    let digits = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+
+    digits.iter().position(|digit| *digit == key)
+}
+
+/// A line typed after `:`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ModalCommand {
+    Write,
+    Quit,
+    WriteQuit,
+    /// 1-based image number, as shown in the labeling index.
+    GoTo(usize),
+    Class(String),
+    /// Any key map action by name, e.g. `:toggle_stats`.
+    Action(String),
+}
+
+impl ModalCommand {
+    pub fn parse(line: &str, actions: &[&str]) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let argument = words.collect::<Vec<_>>().join(" ");
+
+        let command = match (command, argument.is_empty()) {
+            ("w", true) => ModalCommand::Write,
+            ("q", true) => ModalCommand::Quit,
+            ("wq" | "x", true) => ModalCommand::WriteQuit,
+            ("goto", false) => ModalCommand::GoTo(
+                argument
+                    .parse()
+                    .map_err(|_| format!("'{}' is not an image number", argument))?,
+            ),
+            ("class", false) => ModalCommand::Class(argument),
+            (number, true) if number.parse::<usize>().is_ok() => {
+                ModalCommand::GoTo(number.parse().unwrap())
+            }
+            (action, true) if actions.contains(&action) => ModalCommand::Action(action.to_string()),
+            _ => return Err(format!("Unknown command ':{}'", line.trim())),
+        };
+
+        Ok(command)
+    }
+}
+
+/// Work for `apply_modal_actions` decided by the keys handled before `Update`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ModalAction {
+    StepImages(isize),
+    /// Boxes from the selected one on, or the visual selection.
+    DeleteBoxes(usize),
+    Run(ModalCommand),
+}
+
+/// State of the optional vim-style input layer.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ModalState {
+    pub mode: InputMode,
+    /// Digits typed before a command.
+    pub count: Option<usize>,
+    /// Entries collected in visual mode, on the image at `visual_index`.
+    pub visual_selection: BTreeSet<usize>,
+    pub visual_index: isize,
+    pub pending: Vec<ModalAction>,
+}
+
+impl ModalState {
+    pub fn push_digit(&mut self, digit: usize) {
+        let count = self.count.unwrap_or(0) * 10 + digit;
+        if count <= MAX_COUNT {
+            self.count = Some(count);
+        }
+    }
+
+    /// The count typed before a command, 1 when none was, and clears it.
+    pub fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1).max(1)
+    }
+
+    pub fn set_mode(&mut self, mode: InputMode) {
+        self.mode = mode;
+        self.count = None;
+        if mode != InputMode::Visual {
+            self.visual_selection.clear();
+        }
+    }
+
+    /// e.g. "NORMAL 5" while a count is typed, or "VISUAL 3 boxes".
+    pub fn describe(&self) -> String {
+        match (self.mode, self.count) {
+            (InputMode::Visual, _) => format!(
+                "{} {} boxes",
+                self.mode.describe(),
+                self.visual_selection.len()
+            ),
+            (mode, Some(count)) => format!("{} {}", mode.describe(), count),
+            (mode, None) => mode.describe().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let actions = ["toggle_stats", "quit"];
+
+        assert_eq!(ModalCommand::parse("w", &actions), Ok(ModalCommand::Write));
+        assert_eq!(
+            ModalCommand::parse("goto 120", &actions),
+            Ok(ModalCommand::GoTo(120))
+        );
+        assert_eq!(
+            ModalCommand::parse(" 7 ", &actions),
+            Ok(ModalCommand::GoTo(7))
+        );
+        assert_eq!(
+            ModalCommand::parse("class sports car", &actions),
+            Ok(ModalCommand::Class("sports car".to_string()))
+        );
+        assert_eq!(
+            ModalCommand::parse("toggle_stats", &actions),
+            Ok(ModalCommand::Action("toggle_stats".to_string()))
+        );
+        assert!(ModalCommand::parse("goto end", &actions).is_err());
+        assert!(ModalCommand::parse("w now", &actions).is_err());
+        assert!(ModalCommand::parse("explode", &actions).is_err());
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut state = ModalState::default();
+        assert_eq!(state.take_count(), 1);
+
+        state.push_digit(1);
+        state.push_digit(2);
+        assert_eq!(state.describe(), "NORMAL 12");
+        assert_eq!(state.take_count(), 12);
+        assert_eq!(state.count, None);
+
+        for _ in 0..20 {
+            state.push_digit(9);
+        }
+        assert_eq!(state.take_count(), MAX_COUNT);
+    }
+
+    #[test]
+    fn test_counted_entries_stay_within_the_image() {
+        assert_eq!(counted_entries(1, 2, 5), 1..3);
+        assert_eq!(counted_entries(3, MAX_COUNT, 5), 3..5);
+        assert_eq!(counted_entries(usize::MAX, usize::MAX, 5), 5..5);
+    }
+}
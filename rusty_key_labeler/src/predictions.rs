@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::Resource;
+use yolo_io::YoloEntry;
+
+/// A label entry that may carry a model's confidence in a sixth column.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PredictedEntry {
+    pub entry: YoloEntry,
+    pub confidence: Option<f32>,
+}
+
+impl PredictedEntry {
+    /// Entries without a confidence column count as certain.
+    pub fn confidence_or_certain(&self) -> f32 {
+        self.confidence.unwrap_or(1.0)
+    }
+}
+
+/// Parses YOLO label lines of five columns, or six when the last one is a
+/// prediction confidence.
+pub fn parse_predicted_entries(contents: &str) -> Result<Vec<PredictedEntry>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(row, line)| {
+            let columns = line
+                .split_whitespace()
+                .map(|column| column.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|e| format!("line {}: {}", row + 1, e))?;
+
+            match columns.as_slice() {
+                [class, x_center, y_center, width, height, rest @ ..] if rest.len() <= 1 => {
+                    Ok(PredictedEntry {
+                        entry: YoloEntry {
+                            class: *class as isize,
+                            x_center: *x_center,
+                            y_center: *y_center,
+                            width: *width,
+                            height: *height,
+                        },
+                        confidence: rest.first().copied(),
+                    })
+                }
+                _ => Err(format!(
+                    "line {}: expected 5 or 6 columns, found {}",
+                    row + 1,
+                    columns.len()
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Reads `<directory>/<stem>.txt`. A missing file means no predictions.
+pub fn read_predicted_entries(directory: &str, stem: &str) -> Result<Vec<PredictedEntry>, String> {
+    let path = Path::new(directory).join(format!("{}.txt", stem));
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_predicted_entries(&contents).map_err(|e| format!("{:?} {}", path, e))
+}
+
+/// The prediction set's labels for the selected image.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Predictions {
+    pub image_path: Option<PathBuf>,
+    pub entries: Vec<PredictedEntry>,
+    /// Predictions below this confidence are hidden and never accepted.
+    pub threshold: f32,
+}
+
+impl Predictions {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            ..Default::default()
+        }
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &PredictedEntry> {
+        self.entries
+            .iter()
+            .filter(|predicted| predicted.confidence_or_certain() >= self.threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_optional_confidence_column() {
+        let entries =
+            parse_predicted_entries("0 0.5 0.5 0.2 0.2\n1 0.1 0.2 0.1 0.1 0.75\n\n").unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].confidence, None);
+        assert_eq!(entries[1].entry.class, 1);
+        assert_eq!(entries[1].confidence, Some(0.75));
+        assert!(parse_predicted_entries("0 0.5 0.5").is_err());
+    }
+}
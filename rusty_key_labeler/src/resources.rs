@@ -1,6 +1,9 @@
+use bevy::math::Vec2;
 use bevy::prelude::Entity;
 use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use yolo_io::ImageLabelPair;
 use yolo_io::YoloProject;
 use yolo_io::YoloProjectConfig;
 
@@ -16,6 +19,87 @@ pub struct AppData {
     pub yolo_project: YoloProject,
     pub config: Config,
     pub left_panel_eid: Option<Entity>,
+    /// Class given to newly created bounding boxes.
+    pub active_class: Option<isize>,
+}
+
+/// Arrow key hold state for image navigation.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct NavigationRepeat {
+    /// 1 while stepping forward, -1 backward, 0 when no arrow is held.
+    pub direction: isize,
+    /// Seconds until the next repeated step.
+    pub countdown: f32,
+    /// Set once repeating starts; images are only previewed until release.
+    pub scrubbing: bool,
+}
+
+/// Which canvas guides are currently drawn.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct GuideOverlays {
+    pub pixel_grid: bool,
+    pub crosshair: bool,
+}
+
+/// Corner placed by the first `draw_box` press, in image pixels.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct BoxDrawing {
+    pub anchor: Option<Vec2>,
+}
+
+/// What typed text is for once Enter submits it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TextEntryTarget {
+    ImageComment,
+    BoxComment {
+        entry: usize,
+    },
+    /// The modal layer's `:` command line.
+    Command,
+}
+
+/// Free text typed into the bottom panel. While a target is set, keys type
+/// instead of triggering their bindings.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TextEntry {
+    pub target: Option<TextEntryTarget>,
+    pub text: String,
+    /// Set when Enter is pressed, for the target's system to take.
+    pub submitted: Option<(TextEntryTarget, String)>,
+}
+
+impl TextEntry {
+    pub fn start(&mut self, target: TextEntryTarget) {
+        self.target = Some(target);
+        self.text.clear();
+    }
+
+    pub fn take_submitted(&mut self) -> Option<(TextEntryTarget, String)> {
+        self.submitted.take()
+    }
+}
+
+/// A box to select and zoom to once its image's boxes have loaded, as
+/// `(pair index, entry)`.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct PendingBoxFocus(pub Option<(usize, usize)>);
+
+/// Problems found while preparing the app, before Bevy's logger runs.
+/// They are logged once the app starts.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct StartupWarnings(pub Vec<String>);
+
+/// Image given with `--open`, shown first instead of the session's.
+#[derive(Resource, Debug, Clone)]
+pub struct StartImage(pub PathBuf);
+
+impl StartImage {
+    /// Index of the pair whose image path or name matches.
+    pub fn image_index(&self, pairs: &[ImageLabelPair]) -> Option<usize> {
+        pairs.iter().position(|pair| {
+            pair.image_path.as_ref() == Some(&self.0) || self.0.as_os_str() == pair.name.as_str()
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Resource, Clone)]
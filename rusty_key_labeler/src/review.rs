@@ -0,0 +1,337 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::Resource;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use yolo_io::ImageLabelPair;
+
+pub const REVIEW_FILE_NAME: &str = "review.json";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    #[default]
+    Unreviewed,
+    Approved,
+    NeedsFix,
+}
+
+impl ReviewStatus {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ReviewStatus::Unreviewed => "unreviewed",
+            ReviewStatus::Approved => "approved",
+            ReviewStatus::NeedsFix => "needs fix",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoxFlag {
+    WrongClass,
+    LooseFit,
+    MissingNeighbour,
+}
+
+impl BoxFlag {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            BoxFlag::WrongClass => "wrong class",
+            BoxFlag::LooseFit => "loose fit",
+            BoxFlag::MissingNeighbour => "missing neighbour",
+        }
+    }
+
+    /// The flag after `flag` when cycling, ending back at no flag.
+    pub fn cycle(flag: Option<BoxFlag>) -> Option<BoxFlag> {
+        match flag {
+            None => Some(BoxFlag::WrongClass),
+            Some(BoxFlag::WrongClass) => Some(BoxFlag::LooseFit),
+            Some(BoxFlag::LooseFit) => Some(BoxFlag::MissingNeighbour),
+            Some(BoxFlag::MissingNeighbour) => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct BoxReview {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag: Option<BoxFlag>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+}
+
+impl BoxReview {
+    /// The flag and latest comment, e.g. "loose fit: tail is cut off".
+    pub fn describe(&self) -> String {
+        match (self.flag, self.comments.last()) {
+            (Some(flag), Some(comment)) => format!("{}: {}", flag.describe(), comment),
+            (Some(flag), None) => flag.describe().to_string(),
+            (None, Some(comment)) => comment.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct ImageReview {
+    pub status: ReviewStatus,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    /// Keyed by the entry's index in the label file, so deleting an earlier
+    /// box moves its marks onto the next one.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub boxes: BTreeMap<usize, BoxReview>,
+}
+
+/// Review status and comments per image, keyed by the pair's name and
+/// stored in the project's output directory.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize, Resource)]
+pub struct ReviewManifest {
+    pub images: BTreeMap<String, ImageReview>,
+}
+
+impl ReviewManifest {
+    pub fn path(output_path: &str) -> PathBuf {
+        Path::new(output_path).join(REVIEW_FILE_NAME)
+    }
+
+    /// An empty manifest when none has been saved yet.
+    pub fn load(output_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path(output_path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let data = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn save(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(output_path)?;
+        let data = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path(output_path), data)?;
+
+        Ok(())
+    }
+
+    pub fn review(&self, name: &str) -> Option<&ImageReview> {
+        self.images.get(name)
+    }
+
+    pub fn status(&self, name: &str) -> ReviewStatus {
+        self.review(name)
+            .map_or(ReviewStatus::Unreviewed, |review| review.status)
+    }
+
+    /// Sets `status`, or returns the image to unreviewed when it already has it.
+    pub fn toggle_status(&mut self, name: &str, status: ReviewStatus) -> ReviewStatus {
+        let review = self.images.entry(name.to_string()).or_default();
+        review.status = match review.status == status {
+            true => ReviewStatus::Unreviewed,
+            false => status,
+        };
+        let status = review.status;
+
+        self.prune(name);
+        status
+    }
+
+    pub fn add_comment(&mut self, name: &str, comment: String) {
+        self.images
+            .entry(name.to_string())
+            .or_default()
+            .comments
+            .push(comment);
+    }
+
+    pub fn box_review(&self, name: &str, entry: usize) -> Option<&BoxReview> {
+        self.review(name)
+            .and_then(|review| review.boxes.get(&entry))
+    }
+
+    /// Moves the box to the next flag, see `BoxFlag::cycle`.
+    pub fn cycle_box_flag(&mut self, name: &str, entry: usize) -> Option<BoxFlag> {
+        let box_review = self
+            .images
+            .entry(name.to_string())
+            .or_default()
+            .boxes
+            .entry(entry)
+            .or_default();
+        box_review.flag = BoxFlag::cycle(box_review.flag);
+        let flag = box_review.flag;
+
+        self.prune(name);
+        flag
+    }
+
+    pub fn add_box_comment(&mut self, name: &str, entry: usize, comment: String) {
+        self.images
+            .entry(name.to_string())
+            .or_default()
+            .boxes
+            .entry(entry)
+            .or_default()
+            .comments
+            .push(comment);
+    }
+
+    /// Drops the marks of removed entries and moves later marks down with
+    /// their boxes. Returns whether any mark changed.
+    pub fn remove_boxes(&mut self, name: &str, removed: &[usize]) -> bool {
+        let review = match self.images.get_mut(name) {
+            Some(review) if !review.boxes.is_empty() => review,
+            _ => return false,
+        };
+
+        let boxes = std::mem::take(&mut review.boxes);
+        review.boxes = boxes
+            .iter()
+            .filter(|(entry, _)| !removed.contains(entry))
+            .map(|(entry, box_review)| {
+                let shift = removed.iter().filter(|removed| **removed < *entry).count();
+                (entry - shift, box_review.clone())
+            })
+            .collect();
+        let changed = review.boxes != boxes;
+
+        self.prune(name);
+        changed
+    }
+
+    /// `(pair index, entry)` of every flagged or commented box, in project order.
+    /// Marks past the end of a pair's label file are skipped.
+    pub fn marked_boxes(&self, pairs: &[ImageLabelPair]) -> Vec<(usize, usize)> {
+        pairs
+            .iter()
+            .enumerate()
+            .filter_map(|(pair_index, pair)| {
+                let entries = pair
+                    .label_file
+                    .as_ref()
+                    .map_or(0, |label_file| label_file.entries.len());
+                self.review(&pair.name).map(|review| {
+                    (
+                        pair_index,
+                        review.boxes.keys().filter(move |entry| **entry < entries),
+                    )
+                })
+            })
+            .flat_map(|(pair_index, entries)| entries.map(move |entry| (pair_index, *entry)))
+            .collect()
+    }
+
+    /// Every status other than unreviewed, for the navigation filter.
+    pub fn statuses(&self) -> HashMap<String, ReviewStatus> {
+        self.images
+            .iter()
+            .filter(|(_, review)| review.status != ReviewStatus::Unreviewed)
+            .map(|(name, review)| (name.clone(), review.status))
+            .collect()
+    }
+
+    // Keeps the manifest to images with something to say about them.
+    fn prune(&mut self, name: &str) {
+        if let Some(review) = self.images.get_mut(name) {
+            review
+                .boxes
+                .retain(|_, box_review| *box_review != BoxReview::default());
+        }
+
+        if self.images.get(name) == Some(&ImageReview::default()) {
+            self.images.remove(name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_status_and_round_trip() {
+        let mut manifest = ReviewManifest::default();
+
+        assert_eq!(
+            manifest.toggle_status("a", ReviewStatus::Approved),
+            ReviewStatus::Approved
+        );
+        assert_eq!(
+            manifest.toggle_status("b", ReviewStatus::NeedsFix),
+            ReviewStatus::NeedsFix
+        );
+        manifest.add_comment("b", "tail frame is cut off".to_string());
+        assert_eq!(
+            manifest.toggle_status("a", ReviewStatus::Approved),
+            ReviewStatus::Unreviewed
+        );
+
+        assert!(manifest.review("a").is_none());
+        assert_eq!(manifest.status("b"), ReviewStatus::NeedsFix);
+
+        let data = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(
+            data,
+            r#"{"images":{"b":{"status":"needs_fix","comments":["tail frame is cut off"]}}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ReviewManifest>(&data).unwrap(),
+            manifest
+        );
+    }
+
+    #[test]
+    fn test_box_flags_cycle_and_prune() {
+        let mut manifest = ReviewManifest::default();
+
+        let flags = (0..4)
+            .map(|_| manifest.cycle_box_flag("a", 2))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flags,
+            vec![
+                Some(BoxFlag::WrongClass),
+                Some(BoxFlag::LooseFit),
+                Some(BoxFlag::MissingNeighbour),
+                None
+            ]
+        );
+        assert!(manifest.review("a").is_none());
+
+        manifest.cycle_box_flag("a", 1);
+        manifest.add_box_comment("a", 1, "too wide".to_string());
+        assert_eq!(
+            manifest.box_review("a", 1).unwrap().describe(),
+            "wrong class: too wide"
+        );
+    }
+
+    #[test]
+    fn test_removing_boxes_moves_marks_with_their_boxes() {
+        let mut manifest = ReviewManifest::default();
+        manifest.cycle_box_flag("a", 0);
+        manifest.cycle_box_flag("a", 1);
+        manifest.cycle_box_flag("a", 1);
+        manifest.add_box_comment("a", 3, "too wide".to_string());
+
+        assert!(manifest.remove_boxes("a", &[0]));
+        assert_eq!(
+            manifest.box_review("a", 0).unwrap().flag,
+            Some(BoxFlag::LooseFit)
+        );
+        assert!(manifest.box_review("a", 1).is_none());
+        assert_eq!(manifest.box_review("a", 2).unwrap().describe(), "too wide");
+
+        // Only boxes after the marked ones are removed.
+        assert!(!manifest.remove_boxes("a", &[5]));
+        assert!(manifest.remove_boxes("a", &[1]));
+        assert!(manifest.box_review("a", 1).is_some());
+        assert!(manifest.remove_boxes("a", &[0, 1]));
+        assert!(manifest.review("a").is_none());
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{math::Vec2, prelude::Resource};
+use serde::{Deserialize, Serialize};
+use yolo_io::ImageLabelPair;
+
+use crate::filters::ImageFilter;
+
+pub const SESSION_FILE_NAME: &str = "session.json";
+
+/// Where the labeler was when it last ran, stored in the project's output directory.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Resource)]
+pub struct SessionState {
+    pub image_path: PathBuf,
+    pub zoom: f32,
+    pub camera_translation: Vec2,
+    pub filter: ImageFilter,
+    pub active_class: Option<isize>,
+}
+
+impl SessionState {
+    pub fn path(output_path: &str) -> PathBuf {
+        Path::new(output_path).join(SESSION_FILE_NAME)
+    }
+
+    pub fn load(output_path: &str) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let path = Self::path(output_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&data)?))
+    }
+
+    pub fn save(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(output_path)?;
+        let data = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path(output_path), data)?;
+
+        Ok(())
+    }
+
+    /// Index of the session's image among `pairs`, if it is still part of the project.
+    pub fn image_index(&self, pairs: &[ImageLabelPair]) -> Option<usize> {
+        pairs
+            .iter()
+            .position(|pair| pair.image_path.as_ref() == Some(&self.image_path))
+    }
+}
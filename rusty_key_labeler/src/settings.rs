@@ -5,7 +5,14 @@ use bevy::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{bounding_boxes::BoundingBoxSettings, utils::srgba_string_to_color};
+use crate::{
+    bounding_boxes::BoundingBoxSettings,
+    filters::ImageFilter,
+    key_bindings::{find_conflicts, KeyBindings, KeyPress},
+    modal::count_digit,
+    sprite_grid::SpriteGrid,
+    utils::srgba_string_to_color,
+};
 
 pub const MAIN_LAYER: RenderLayers = RenderLayers::layer(0);
 pub const UI_LAYER: RenderLayers = RenderLayers::layer(1);
@@ -17,29 +24,291 @@ pub const UI_INNER_BORDER_COLOR: Color = Color::WHITE;
 pub const UI_OUTER_BORDER_COLOR: Color = Color::WHITE;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct KeyMap {
-    pub zoom_in: KeyCode,
-    pub zoom_out: KeyCode,
-    pub pan_up: KeyCode,
-    pub pan_down: KeyCode,
-    pub pan_left: KeyCode,
-    pub pan_right: KeyCode,
-    pub cycle_selection: KeyCode,
-    pub change_selection: KeyCode,
+    pub zoom_in: KeyBindings,
+    pub zoom_out: KeyBindings,
+    pub pan_up: KeyBindings,
+    pub pan_down: KeyBindings,
+    pub pan_left: KeyBindings,
+    pub pan_right: KeyBindings,
+    /// Held to keep stepping through images.
+    pub next_image: KeyBindings,
+    pub previous_image: KeyBindings,
+    pub cycle_selection: KeyBindings,
+    pub change_selection: KeyBindings,
+    pub cycle_filter: KeyBindings,
+    pub toggle_minimap: KeyBindings,
+    pub zoom_fit_image: KeyBindings,
+    pub zoom_fit_selection: KeyBindings,
+    pub zoom_actual_pixels: KeyBindings,
+    pub zoom_2x: KeyBindings,
+    pub zoom_4x: KeyBindings,
+    pub zoom_8x: KeyBindings,
+    pub toggle_pixel_grid: KeyBindings,
+    pub toggle_crosshair: KeyBindings,
+    pub draw_box: KeyBindings,
+    pub move_corner: KeyBindings,
+    /// Drops a corner placed with `draw_box`.
+    pub cancel_box: KeyBindings,
+    /// Held to place box corners without snapping.
+    pub disable_snapping: KeyBindings,
+    pub suggest_boxes: KeyBindings,
+    pub toggle_sprite_grid: KeyBindings,
+    pub label_grid_cells: KeyBindings,
+    pub label_grid_rows: KeyBindings,
+    pub label_grid_columns: KeyBindings,
+    pub shrink_box: KeyBindings,
+    pub shrink_all_boxes: KeyBindings,
+    pub run_detector: KeyBindings,
+    pub accept_confident_proposals: KeyBindings,
+    pub raise_confidence_threshold: KeyBindings,
+    pub lower_confidence_threshold: KeyBindings,
+    pub accept_predictions: KeyBindings,
+    pub next_disagreement: KeyBindings,
+    pub toggle_stats: KeyBindings,
+    pub next_issue: KeyBindings,
+    pub approve_image: KeyBindings,
+    pub flag_needs_fix: KeyBindings,
+    pub comment_image: KeyBindings,
+    pub flag_box: KeyBindings,
+    pub comment_box: KeyBindings,
+    pub next_flagged_box: KeyBindings,
+    pub delete_box: KeyBindings,
+    pub toggle_help: KeyBindings,
+    pub quit: KeyBindings,
 }
 
 impl Default for KeyMap {
     fn default() -> Self {
         Self {
-            zoom_in: KeyCode::KeyE,
-            zoom_out: KeyCode::KeyQ,
-            pan_up: KeyCode::KeyW,
-            pan_down: KeyCode::KeyS,
-            pan_left: KeyCode::KeyA,
-            pan_right: KeyCode::KeyD,
-            cycle_selection: KeyCode::Tab,
-            change_selection: KeyCode::Space,
+            zoom_in: KeyCode::KeyE.into(),
+            zoom_out: KeyCode::KeyQ.into(),
+            pan_up: KeyCode::KeyW.into(),
+            pan_down: KeyCode::KeyS.into(),
+            pan_left: KeyCode::KeyA.into(),
+            pan_right: KeyCode::KeyD.into(),
+            next_image: KeyCode::ArrowRight.into(),
+            previous_image: KeyCode::ArrowLeft.into(),
+            cycle_selection: KeyCode::Tab.into(),
+            change_selection: KeyCode::Space.into(),
+            cycle_filter: KeyCode::KeyF.into(),
+            toggle_minimap: KeyCode::KeyM.into(),
+            zoom_fit_image: KeyCode::Digit0.into(),
+            zoom_fit_selection: KeyCode::KeyZ.into(),
+            zoom_actual_pixels: KeyCode::Digit1.into(),
+            zoom_2x: KeyCode::Digit2.into(),
+            zoom_4x: KeyCode::Digit4.into(),
+            zoom_8x: KeyCode::Digit8.into(),
+            toggle_pixel_grid: KeyCode::KeyG.into(),
+            toggle_crosshair: KeyCode::KeyC.into(),
+            draw_box: KeyCode::KeyB.into(),
+            move_corner: KeyCode::KeyR.into(),
+            cancel_box: KeyCode::Escape.into(),
+            disable_snapping: KeyCode::ShiftLeft.into(),
+            suggest_boxes: KeyCode::KeyP.into(),
+            toggle_sprite_grid: KeyCode::KeyT.into(),
+            label_grid_cells: KeyCode::KeyY.into(),
+            label_grid_rows: KeyCode::KeyH.into(),
+            label_grid_columns: KeyCode::KeyV.into(),
+            shrink_box: KeyCode::KeyK.into(),
+            shrink_all_boxes: KeyCode::KeyL.into(),
+            run_detector: KeyCode::KeyO.into(),
+            accept_confident_proposals: KeyCode::KeyU.into(),
+            raise_confidence_threshold: KeyCode::BracketRight.into(),
+            lower_confidence_threshold: KeyCode::BracketLeft.into(),
+            accept_predictions: KeyCode::KeyN.into(),
+            next_disagreement: KeyCode::KeyJ.into(),
+            toggle_stats: KeyCode::KeyI.into(),
+            next_issue: KeyCode::KeyX.into(),
+            approve_image: KeyCode::Period.into(),
+            flag_needs_fix: KeyCode::Comma.into(),
+            comment_image: KeyCode::Semicolon.into(),
+            flag_box: KeyCode::Quote.into(),
+            comment_box: KeyCode::Slash.into(),
+            next_flagged_box: KeyCode::Backslash.into(),
+            delete_box: [KeyCode::Delete, KeyCode::Backspace].into(),
+            toggle_help: KeyCode::F1.into(),
+            quit: KeyPress::new(KeyCode::KeyQ).ctrl().into(),
+        }
+    }
+}
+
+/// Help overlay groups, in display order. Actions missing here are shown
+/// under "Other".
+const KEY_MAP_SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "Navigation",
+        &[
+            "next_image",
+            "previous_image",
+            "cycle_filter",
+            "next_issue",
+            "next_disagreement",
+            "next_flagged_box",
+        ],
+    ),
+    (
+        "View",
+        &[
+            "zoom_in",
+            "zoom_out",
+            "pan_up",
+            "pan_down",
+            "pan_left",
+            "pan_right",
+            "zoom_fit_image",
+            "zoom_fit_selection",
+            "zoom_actual_pixels",
+            "zoom_2x",
+            "zoom_4x",
+            "zoom_8x",
+            "toggle_pixel_grid",
+            "toggle_crosshair",
+            "toggle_minimap",
+            "toggle_stats",
+        ],
+    ),
+    (
+        "Editing",
+        &[
+            "cycle_selection",
+            "change_selection",
+            "draw_box",
+            "move_corner",
+            "cancel_box",
+            "disable_snapping",
+            "delete_box",
+            "shrink_box",
+            "shrink_all_boxes",
+            "toggle_sprite_grid",
+            "label_grid_cells",
+            "label_grid_rows",
+            "label_grid_columns",
+        ],
+    ),
+    (
+        "Suggestions",
+        &[
+            "suggest_boxes",
+            "run_detector",
+            "accept_confident_proposals",
+            "raise_confidence_threshold",
+            "lower_confidence_threshold",
+            "accept_predictions",
+        ],
+    ),
+    (
+        "Review",
+        &[
+            "approve_image",
+            "flag_needs_fix",
+            "comment_image",
+            "flag_box",
+            "comment_box",
+        ],
+    ),
+    ("General", &["toggle_help", "quit"]),
+];
+
+impl KeyMap {
+    /// Every action's name and bindings.
+    pub fn actions(&self) -> Vec<(&'static str, &KeyBindings)> {
+        vec![
+            ("zoom_in", &self.zoom_in),
+            ("zoom_out", &self.zoom_out),
+            ("pan_up", &self.pan_up),
+            ("pan_down", &self.pan_down),
+            ("pan_left", &self.pan_left),
+            ("pan_right", &self.pan_right),
+            ("next_image", &self.next_image),
+            ("previous_image", &self.previous_image),
+            ("cycle_selection", &self.cycle_selection),
+            ("change_selection", &self.change_selection),
+            ("cycle_filter", &self.cycle_filter),
+            ("toggle_minimap", &self.toggle_minimap),
+            ("zoom_fit_image", &self.zoom_fit_image),
+            ("zoom_fit_selection", &self.zoom_fit_selection),
+            ("zoom_actual_pixels", &self.zoom_actual_pixels),
+            ("zoom_2x", &self.zoom_2x),
+            ("zoom_4x", &self.zoom_4x),
+            ("zoom_8x", &self.zoom_8x),
+            ("toggle_pixel_grid", &self.toggle_pixel_grid),
+            ("toggle_crosshair", &self.toggle_crosshair),
+            ("draw_box", &self.draw_box),
+            ("move_corner", &self.move_corner),
+            ("cancel_box", &self.cancel_box),
+            ("disable_snapping", &self.disable_snapping),
+            ("suggest_boxes", &self.suggest_boxes),
+            ("toggle_sprite_grid", &self.toggle_sprite_grid),
+            ("label_grid_cells", &self.label_grid_cells),
+            ("label_grid_rows", &self.label_grid_rows),
+            ("label_grid_columns", &self.label_grid_columns),
+            ("shrink_box", &self.shrink_box),
+            ("shrink_all_boxes", &self.shrink_all_boxes),
+            ("run_detector", &self.run_detector),
+            (
+                "accept_confident_proposals",
+                &self.accept_confident_proposals,
+            ),
+            (
+                "raise_confidence_threshold",
+                &self.raise_confidence_threshold,
+            ),
+            (
+                "lower_confidence_threshold",
+                &self.lower_confidence_threshold,
+            ),
+            ("accept_predictions", &self.accept_predictions),
+            ("next_disagreement", &self.next_disagreement),
+            ("toggle_stats", &self.toggle_stats),
+            ("next_issue", &self.next_issue),
+            ("approve_image", &self.approve_image),
+            ("flag_needs_fix", &self.flag_needs_fix),
+            ("comment_image", &self.comment_image),
+            ("flag_box", &self.flag_box),
+            ("comment_box", &self.comment_box),
+            ("next_flagged_box", &self.next_flagged_box),
+            ("delete_box", &self.delete_box),
+            ("toggle_help", &self.toggle_help),
+            ("quit", &self.quit),
+        ]
+    }
+
+    /// Actions grouped for the help overlay.
+    pub fn sections(&self) -> Vec<(&'static str, Vec<(&'static str, &KeyBindings)>)> {
+        let actions = self.actions();
+        let mut sections = KEY_MAP_SECTIONS
+            .iter()
+            .map(|(title, names)| {
+                let section = actions
+                    .iter()
+                    .filter(|(name, _)| names.contains(name))
+                    .copied()
+                    .collect::<Vec<_>>();
+                (*title, section)
+            })
+            .collect::<Vec<_>>();
+
+        let other = actions
+            .iter()
+            .filter(|(name, _)| {
+                !KEY_MAP_SECTIONS
+                    .iter()
+                    .any(|(_, names)| names.contains(name))
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        if !other.is_empty() {
+            sections.push(("Other", other));
         }
+
+        sections
+    }
+
+    /// Bindings that can't all work, see `find_conflicts`.
+    pub fn conflicts(&self) -> Vec<String> {
+        find_conflicts(&self.actions())
     }
 }
 
@@ -55,6 +324,370 @@ impl Default for PanFactor {
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyRepeatSettings {
+    /// Seconds an arrow key is held before images start repeating.
+    pub initial_delay: f32,
+    /// Images stepped per second while repeating. Repeated steps only show
+    /// the file name and a thumbnail; the full image loads on release.
+    pub repeat_rate: f32,
+}
+
+impl Default for KeyRepeatSettings {
+    fn default() -> Self {
+        Self {
+            initial_delay: 0.35,
+            repeat_rate: 12.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrefetchSettings {
+    /// Images decoded ahead of time on each side of the current one.
+    pub neighbours: usize,
+    pub memory_budget_mb: usize,
+}
+
+impl Default for PrefetchSettings {
+    fn default() -> Self {
+        Self {
+            neighbours: 2,
+            memory_budget_mb: 512,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilmstripSettings {
+    pub enabled: bool,
+    /// Thumbnails shown on each side of the current image.
+    pub neighbours: usize,
+    /// Longest edge of a thumbnail in pixels.
+    pub thumbnail_size: u32,
+}
+
+impl Default for FilmstripSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            neighbours: 3,
+            thumbnail_size: 96,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MinimapSettings {
+    pub enabled: bool,
+    /// Width of the minimap in pixels; its height follows the image.
+    pub width: f32,
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            width: 200.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuideSettings {
+    /// Screen pixels per image pixel at which the pixel grid appears.
+    pub pixel_grid_min_zoom: f32,
+}
+
+impl Default for GuideSettings {
+    fn default() -> Self {
+        Self {
+            pixel_grid_min_zoom: 8.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapSettings {
+    pub enabled: bool,
+    /// Round to whole image pixels when no edge or grid applies.
+    pub pixels: bool,
+    /// Grid cell size in image pixels, e.g. a sprite's frame size. 0 disables it.
+    pub grid_width: f32,
+    pub grid_height: f32,
+    pub box_edges: bool,
+    pub image_edges: bool,
+    /// Distance in image pixels within which edges attract a corner.
+    pub tolerance: f32,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pixels: true,
+            grid_width: 0.0,
+            grid_height: 0.0,
+            box_edges: true,
+            image_edges: true,
+            tolerance: 4.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuggestionSettings {
+    /// Pixels with alpha above this count as content.
+    pub alpha_threshold: u8,
+    /// Regions this many pixels apart or closer become one suggestion.
+    pub merge_gap: u32,
+    /// Suggestions narrower or shorter than this are treated as noise.
+    pub min_size: f32,
+    /// Suggested, detected, sprite grid and accepted prediction boxes
+    /// overlapping an existing box by more IoU than this are skipped.
+    pub max_overlap: f32,
+}
+
+impl Default for SuggestionSettings {
+    fn default() -> Self {
+        Self {
+            alpha_threshold: 0,
+            merge_gap: 2,
+            min_size: 2.0,
+            max_overlap: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpriteGridSettings {
+    /// Used instead of detecting the grid from the image when set.
+    pub grid: Option<SpriteGrid>,
+    /// Cells with no pixel of alpha above this are empty.
+    pub alpha_threshold: u8,
+    /// Frame size used to split detected frames that touch, i.e. sheets
+    /// packed with no spacing.
+    pub frame_width: Option<u32>,
+    pub frame_height: Option<u32>,
+    /// Class names given to boxes spanning a row or a column of frames.
+    pub row_class: String,
+    pub column_class: String,
+}
+
+impl Default for SpriteGridSettings {
+    fn default() -> Self {
+        Self {
+            grid: None,
+            alpha_threshold: 0,
+            frame_width: None,
+            frame_height: None,
+            row_class: "horizontal_animation".to_string(),
+            column_class: "vertical_animation".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShrinkSettings {
+    /// Pixels with alpha at or below this are background.
+    pub alpha_threshold: u8,
+    /// Largest per-channel difference from the border's median colour
+    /// still counted as background.
+    pub color_tolerance: u8,
+}
+
+impl Default for ShrinkSettings {
+    fn default() -> Self {
+        Self {
+            alpha_threshold: 0,
+            color_tolerance: 16,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LintSettings {
+    /// Boxes narrower or shorter than this many image pixels are reported.
+    pub min_size: f32,
+    /// IoU above which two boxes of the same class are near-duplicates.
+    pub duplicate_iou: f32,
+}
+
+impl Default for LintSettings {
+    fn default() -> Self {
+        Self {
+            min_size: 2.0,
+            duplicate_iou: 0.8,
+        }
+    }
+}
+
+/// Keys of the vim-style input layer. They shadow the main key map while
+/// their mode is active; chords aren't supported here.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModalKeyMap {
+    pub normal_mode: KeyBindings,
+    pub insert_mode: KeyBindings,
+    pub visual_mode: KeyBindings,
+    pub command_line: KeyBindings,
+    /// Take a count prefix, e.g. `5n`.
+    pub next_image: KeyBindings,
+    pub previous_image: KeyBindings,
+    pub delete_boxes: KeyBindings,
+}
+
+impl Default for ModalKeyMap {
+    fn default() -> Self {
+        Self {
+            normal_mode: KeyCode::Escape.into(),
+            insert_mode: KeyCode::KeyI.into(),
+            visual_mode: KeyCode::KeyV.into(),
+            command_line: KeyPress::new(KeyCode::Semicolon).shift().into(),
+            next_image: KeyCode::KeyN.into(),
+            previous_image: KeyPress::new(KeyCode::KeyN).shift().into(),
+            delete_boxes: KeyCode::KeyX.into(),
+        }
+    }
+}
+
+impl ModalKeyMap {
+    pub fn actions(&self) -> Vec<(&'static str, &KeyBindings)> {
+        vec![
+            ("normal_mode", &self.normal_mode),
+            ("insert_mode", &self.insert_mode),
+            ("visual_mode", &self.visual_mode),
+            ("command_line", &self.command_line),
+            ("next_image", &self.next_image),
+            ("previous_image", &self.previous_image),
+            ("delete_boxes", &self.delete_boxes),
+        ]
+    }
+
+    pub fn conflicts(&self) -> Vec<String> {
+        find_conflicts(&self.actions())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ModalSettings {
+    /// Starts in normal mode, where box drawing keys are off until insert mode.
+    pub enabled: bool,
+    pub key_map: ModalKeyMap,
+}
+
+impl ModalSettings {
+    /// Conflicts within the modal key map and, when modes are enabled,
+    /// `key_map` bindings the modal keys and counts take outside insert mode.
+    pub fn conflicts(&self, key_map: &KeyMap) -> Vec<String> {
+        let mut conflicts = self.key_map.conflicts();
+        if !self.enabled {
+            return conflicts;
+        }
+
+        let modal_actions = self.key_map.actions();
+        for (action, bindings) in key_map.actions() {
+            // Boxes are only drawn in insert mode, which the modal keys leave
+            // alone, and leaving it drops the corner anyway.
+            if action == "cancel_box" {
+                continue;
+            }
+
+            for binding in bindings.0.iter() {
+                let first = binding.0[0];
+                let taken_by = modal_actions
+                    .iter()
+                    .find(|(_, modal_bindings)| {
+                        modal_bindings
+                            .0
+                            .iter()
+                            .any(|modal| !modal.is_chord() && modal.0[0] == first)
+                    })
+                    .map(|(modal_action, _)| *modal_action)
+                    // 0 only continues a count.
+                    .or_else(|| {
+                        count_digit(first.key)
+                            .filter(|digit| *digit > 0)
+                            .map(|_| "counts")
+                    });
+
+                if let Some(modal_action) = taken_by {
+                    conflicts.push(format!(
+                        "{} ({}) is taken by modal {} outside insert mode, use :{}",
+                        binding, action, modal_action, action
+                    ));
+                }
+            }
+        }
+
+        conflicts
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DetectorSettings {
+    /// Program speaking the JSON protocol in `detector.rs`. Unset disables it.
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    /// Runs the detector on every image as it is shown.
+    pub auto_run: bool,
+    /// Confidence at or above which proposals are bulk accepted.
+    pub accept_threshold: f32,
+    /// Seconds a detector may run before it is killed.
+    pub timeout_seconds: f32,
+}
+
+impl Default for DetectorSettings {
+    fn default() -> Self {
+        Self {
+            command: None,
+            args: vec![],
+            auto_run: false,
+            accept_threshold: 0.5,
+            timeout_seconds: 30.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PredictionSettings {
+    /// Directory of predicted label files, matched to images by stem. Lines
+    /// may carry a confidence as a sixth column.
+    pub labels: Option<String>,
+    /// Initial confidence below which predictions are hidden.
+    pub confidence_threshold: f32,
+    pub threshold_step: f32,
+    /// IoU at which a prediction matches a ground truth box of its class.
+    pub match_iou: f32,
+    /// Images listed in the evaluation report's worst images.
+    pub report_worst_images: usize,
+}
+
+impl Default for PredictionSettings {
+    fn default() -> Self {
+        Self {
+            labels: None,
+            confidence_threshold: 0.25,
+            threshold_step: 0.05,
+            match_iou: 0.5,
+            report_worst_images: 20,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct UiPanelSize {
     pub width_percentage: f32,
@@ -166,8 +799,35 @@ pub struct Settings {
     pub key_map: KeyMap,
     pub bounding_boxes: BoundingBoxSettings,
     pub ui_panel: UiPanelSettings,
-    pub delay_between_images: f32,
+    #[serde(default)]
+    pub key_repeat: KeyRepeatSettings,
     pub fit_padding_px: f32,
+    #[serde(default)]
+    pub navigation_filter: ImageFilter,
+    #[serde(default)]
+    pub prefetch: PrefetchSettings,
+    #[serde(default)]
+    pub filmstrip: FilmstripSettings,
+    #[serde(default)]
+    pub minimap: MinimapSettings,
+    #[serde(default)]
+    pub guides: GuideSettings,
+    #[serde(default)]
+    pub snapping: SnapSettings,
+    #[serde(default)]
+    pub suggestions: SuggestionSettings,
+    #[serde(default)]
+    pub sprite_grid: SpriteGridSettings,
+    #[serde(default)]
+    pub shrink: ShrinkSettings,
+    #[serde(default)]
+    pub detector: DetectorSettings,
+    #[serde(default)]
+    pub predictions: PredictionSettings,
+    #[serde(default)]
+    pub lint: LintSettings,
+    #[serde(default)]
+    pub modal: ModalSettings,
 }
 
 impl Default for Settings {
@@ -178,8 +838,21 @@ impl Default for Settings {
             key_map: KeyMap::default(),
             bounding_boxes: BoundingBoxSettings::default(),
             ui_panel: UiPanelSettings::default(),
-            delay_between_images: 0.1,
+            key_repeat: KeyRepeatSettings::default(),
             fit_padding_px: 20.0,
+            navigation_filter: ImageFilter::All,
+            prefetch: PrefetchSettings::default(),
+            filmstrip: FilmstripSettings::default(),
+            minimap: MinimapSettings::default(),
+            guides: GuideSettings::default(),
+            snapping: SnapSettings::default(),
+            suggestions: SuggestionSettings::default(),
+            sprite_grid: SpriteGridSettings::default(),
+            shrink: ShrinkSettings::default(),
+            detector: DetectorSettings::default(),
+            predictions: PredictionSettings::default(),
+            lint: LintSettings::default(),
+            modal: ModalSettings::default(),
         }
     }
 }
@@ -210,14 +883,54 @@ mod tests {
         assert_eq!(
             key_map,
             KeyMap {
-                zoom_in: KeyCode::Equal,
-                zoom_out: KeyCode::Minus,
-                pan_up: KeyCode::KeyW,
-                pan_down: KeyCode::KeyS,
-                pan_left: KeyCode::KeyA,
-                pan_right: KeyCode::KeyD,
-                cycle_selection: KeyCode::Tab,
-                change_selection: KeyCode::Space
+                zoom_in: KeyCode::Equal.into(),
+                zoom_out: KeyCode::Minus.into(),
+                pan_up: KeyCode::KeyW.into(),
+                pan_down: KeyCode::KeyS.into(),
+                pan_left: KeyCode::KeyA.into(),
+                pan_right: KeyCode::KeyD.into(),
+                next_image: KeyCode::ArrowRight.into(),
+                previous_image: KeyCode::ArrowLeft.into(),
+                cycle_selection: KeyCode::Tab.into(),
+                change_selection: KeyCode::Space.into(),
+                cycle_filter: KeyCode::KeyF.into(),
+                toggle_minimap: KeyCode::KeyM.into(),
+                zoom_fit_image: KeyCode::Digit0.into(),
+                zoom_fit_selection: KeyCode::KeyZ.into(),
+                zoom_actual_pixels: KeyCode::Digit1.into(),
+                zoom_2x: KeyCode::Digit2.into(),
+                zoom_4x: KeyCode::Digit4.into(),
+                zoom_8x: KeyCode::Digit8.into(),
+                toggle_pixel_grid: KeyCode::KeyG.into(),
+                toggle_crosshair: KeyCode::KeyC.into(),
+                draw_box: KeyCode::KeyB.into(),
+                move_corner: KeyCode::KeyR.into(),
+                cancel_box: KeyCode::Escape.into(),
+                disable_snapping: KeyCode::ShiftLeft.into(),
+                suggest_boxes: KeyCode::KeyP.into(),
+                toggle_sprite_grid: KeyCode::KeyT.into(),
+                label_grid_cells: KeyCode::KeyY.into(),
+                label_grid_rows: KeyCode::KeyH.into(),
+                label_grid_columns: KeyCode::KeyV.into(),
+                shrink_box: KeyCode::KeyK.into(),
+                shrink_all_boxes: KeyCode::KeyL.into(),
+                run_detector: KeyCode::KeyO.into(),
+                accept_confident_proposals: KeyCode::KeyU.into(),
+                raise_confidence_threshold: KeyCode::BracketRight.into(),
+                lower_confidence_threshold: KeyCode::BracketLeft.into(),
+                accept_predictions: KeyCode::KeyN.into(),
+                next_disagreement: KeyCode::KeyJ.into(),
+                toggle_stats: KeyCode::KeyI.into(),
+                next_issue: KeyCode::KeyX.into(),
+                approve_image: KeyCode::Period.into(),
+                flag_needs_fix: KeyCode::Comma.into(),
+                comment_image: KeyCode::Semicolon.into(),
+                flag_box: KeyCode::Quote.into(),
+                comment_box: KeyCode::Slash.into(),
+                next_flagged_box: KeyCode::Backslash.into(),
+                delete_box: [KeyCode::Delete, KeyCode::Backspace].into(),
+                toggle_help: KeyCode::F1.into(),
+                quit: KeyPress::new(KeyCode::KeyQ).ctrl().into(),
             }
         );
     }
@@ -252,9 +965,61 @@ mod tests {
                     font_size: 16.0,
                     font_path: "RobotoMono-Regular.ttf".to_string()
                 },
-                delay_between_images: 0.1,
+                key_repeat: KeyRepeatSettings::default(),
                 fit_padding_px: 20.0,
+                navigation_filter: ImageFilter::All,
+                prefetch: PrefetchSettings::default(),
+                filmstrip: FilmstripSettings::default(),
+                minimap: MinimapSettings::default(),
+                guides: GuideSettings::default(),
+                snapping: SnapSettings::default(),
+                suggestions: SuggestionSettings::default(),
+                sprite_grid: SpriteGridSettings::default(),
+                shrink: ShrinkSettings::default(),
+                detector: DetectorSettings::default(),
+                predictions: PredictionSettings::default(),
+                lint: LintSettings::default(),
+                modal: ModalSettings::default(),
             }
         );
     }
+
+    #[test]
+    fn test_modal_keys_report_the_bindings_they_take() {
+        let key_map = KeyMap::default();
+        let mut modal = ModalSettings::default();
+        assert_eq!(modal.conflicts(&key_map), Vec::<String>::new());
+
+        modal.enabled = true;
+        let conflicts = modal.conflicts(&key_map);
+        assert!(conflicts.contains(
+            &"KeyI (toggle_stats) is taken by modal insert_mode outside insert mode, use :toggle_stats"
+                .to_string()
+        ));
+        assert!(conflicts
+            .iter()
+            .any(|conflict| conflict
+                .starts_with("Digit1 (zoom_actual_pixels) is taken by modal counts")));
+        assert!(!conflicts
+            .iter()
+            .any(|conflict| conflict.contains("zoom_fit_image")));
+        assert!(!conflicts
+            .iter()
+            .any(|conflict| conflict.contains("cancel_box")));
+    }
+
+    #[test]
+    fn test_every_default_action_has_a_help_section() {
+        let key_map = KeyMap::default();
+        let sections = key_map.sections();
+
+        assert!(sections.iter().all(|(title, _)| *title != "Other"));
+        assert_eq!(
+            sections
+                .iter()
+                .map(|(_, actions)| actions.len())
+                .sum::<usize>(),
+            key_map.actions().len()
+        );
+    }
 }
@@ -0,0 +1,134 @@
+use bevy::math::Rect;
+
+use crate::settings::ShrinkSettings;
+
+/// Contracts each edge of `rect` to the first row or column holding a
+/// foreground pixel. Pixels are background when their alpha is at most
+/// the threshold or, when most of the rect's border is opaque, their colour
+/// is within tolerance of the border's median colour. Returns `None` when
+/// the rect holds no foreground.
+pub fn shrink_to_content(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    rect: Rect,
+    settings: &ShrinkSettings,
+) -> Option<Rect> {
+    let rect = rect.intersect(Rect::new(0.0, 0.0, width as f32, height as f32));
+    let (min_x, min_y) = (rect.min.x.floor() as u32, rect.min.y.floor() as u32);
+    let (max_x, max_y) = (rect.max.x.ceil() as u32, rect.max.y.ceil() as u32);
+    if min_x >= max_x || min_y >= max_y {
+        return None;
+    }
+
+    let pixel = |x: u32, y: u32| {
+        let offset = ((y * width + x) * 4) as usize;
+        [
+            rgba[offset],
+            rgba[offset + 1],
+            rgba[offset + 2],
+            rgba[offset + 3],
+        ]
+    };
+
+    let border = (min_x..max_x)
+        .flat_map(|x| [pixel(x, min_y), pixel(x, max_y - 1)])
+        .chain((min_y..max_y).flat_map(|y| [pixel(min_x, y), pixel(max_x - 1, y)]))
+        .collect::<Vec<[u8; 4]>>();
+    let opaque_border = border
+        .iter()
+        .filter(|color| color[3] > settings.alpha_threshold)
+        .copied()
+        .collect::<Vec<[u8; 4]>>();
+    let background = match opaque_border.len() * 2 > border.len() {
+        true => Some(median_color(&opaque_border)),
+        false => None,
+    };
+
+    let is_foreground = |x: u32, y: u32| {
+        let [r, g, b, a] = pixel(x, y);
+        a > settings.alpha_threshold
+            && background.is_none_or(|background| {
+                [r, g, b]
+                    .iter()
+                    .zip(background.iter())
+                    .any(|(channel, median)| channel.abs_diff(*median) > settings.color_tolerance)
+            })
+    };
+
+    let column_has_content = |x: u32| (min_y..max_y).any(|y| is_foreground(x, y));
+    let row_has_content = |y: u32| (min_x..max_x).any(|x| is_foreground(x, y));
+
+    let left = (min_x..max_x).find(|x| column_has_content(*x))?;
+    let right = (left..max_x).rev().find(|x| column_has_content(*x))?;
+    let top = (min_y..max_y).find(|y| row_has_content(*y))?;
+    let bottom = (top..max_y).rev().find(|y| row_has_content(*y))?;
+
+    Some(Rect::new(
+        left as f32,
+        top as f32,
+        (right + 1) as f32,
+        (bottom + 1) as f32,
+    ))
+}
+
+/// Per-channel median of the colours, ignoring alpha.
+fn median_color(colors: &[[u8; 4]]) -> [u8; 3] {
+    let mut median = [0; 3];
+
+    for (channel, value) in median.iter_mut().enumerate() {
+        let mut values = colors
+            .iter()
+            .map(|color| color[channel])
+            .collect::<Vec<u8>>();
+        values.sort_unstable();
+        *value = values.get(values.len() / 2).copied().unwrap_or(0);
+    }
+
+    median
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shrinks_to_transparent_and_solid_backgrounds() {
+        // An opaque white 10x10 image with a red 3x2 block at (4, 5).
+        let (width, height) = (10, 10);
+        let mut rgba = vec![255; (width * height * 4) as usize];
+        for y in 5..7 {
+            for x in 4..7 {
+                let offset = ((y * width + x) * 4) as usize;
+                rgba[offset + 1] = 0;
+                rgba[offset + 2] = 0;
+            }
+        }
+
+        let settings = ShrinkSettings::default();
+        let loose = Rect::new(1.0, 2.0, 9.0, 9.0);
+        assert_eq!(
+            shrink_to_content(&rgba, width, height, loose, &settings),
+            Some(Rect::new(4.0, 5.0, 7.0, 7.0))
+        );
+
+        // Making the white transparent gives the same box.
+        for pixel in rgba.chunks_mut(4).filter(|pixel| pixel[1] == 255) {
+            pixel[3] = 0;
+        }
+        assert_eq!(
+            shrink_to_content(&rgba, width, height, loose, &settings),
+            Some(Rect::new(4.0, 5.0, 7.0, 7.0))
+        );
+        assert_eq!(
+            shrink_to_content(
+                &rgba,
+                width,
+                height,
+                Rect::new(0.0, 0.0, 3.0, 3.0),
+                &settings
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use bevy::math::{Rect, Vec2};
+
+use crate::settings::SnapSettings;
+
+/// Snaps points in image pixel space to the edges of the image and of other
+/// boxes, then to the configured grid or whole pixels.
+#[derive(Debug, Clone)]
+pub struct Snapper {
+    settings: SnapSettings,
+    image_size: Vec2,
+    /// Pixel rects of the boxes not being edited.
+    others: Vec<Rect>,
+}
+
+impl Snapper {
+    pub fn new(settings: &SnapSettings, image_size: Vec2, others: Vec<Rect>) -> Self {
+        Self {
+            settings: settings.clone(),
+            image_size,
+            others,
+        }
+    }
+
+    /// Snaps `point` unless snapping is disabled, always keeping it on the image.
+    pub fn snap_point(&self, point: Vec2) -> Vec2 {
+        let snapped = match self.settings.enabled {
+            true => Vec2::new(self.snap_axis(point.x, 0), self.snap_axis(point.y, 1)),
+            false => point,
+        };
+
+        snapped.clamp(Vec2::ZERO, self.image_size)
+    }
+
+    fn snap_axis(&self, value: f32, axis: usize) -> f32 {
+        let mut edges = Vec::new();
+
+        if self.settings.image_edges {
+            edges.extend([0.0, self.image_size[axis]]);
+        }
+
+        if self.settings.box_edges {
+            for rect in self.others.iter() {
+                edges.extend([rect.min[axis], rect.max[axis]]);
+            }
+        }
+
+        let nearest_edge = edges
+            .into_iter()
+            .filter(|edge| (edge - value).abs() <= self.settings.tolerance)
+            .min_by(|a, b| (a - value).abs().total_cmp(&(b - value).abs()));
+
+        if let Some(edge) = nearest_edge {
+            return edge;
+        }
+
+        let grid = [self.settings.grid_width, self.settings.grid_height][axis];
+        if grid > 0.0 {
+            return (value / grid).round() * grid;
+        }
+
+        match self.settings.pixels {
+            true => value.round(),
+            false => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snaps_to_nearby_edges_before_pixels() {
+        let others = vec![Rect::new(10.0, 10.0, 20.0, 20.0)];
+        let snapper = Snapper::new(&SnapSettings::default(), Vec2::new(100.0, 50.0), others);
+
+        assert_eq!(
+            snapper.snap_point(Vec2::new(21.5, 2.0)),
+            Vec2::new(20.0, 0.0)
+        );
+        assert_eq!(
+            snapper.snap_point(Vec2::new(40.4, 30.6)),
+            Vec2::new(40.0, 31.0)
+        );
+        assert_eq!(
+            snapper.snap_point(Vec2::new(98.0, 60.0)),
+            Vec2::new(100.0, 50.0)
+        );
+    }
+
+    #[test]
+    fn test_snaps_to_grid_and_can_be_disabled() {
+        let settings = SnapSettings {
+            grid_width: 16.0,
+            grid_height: 16.0,
+            image_edges: false,
+            ..Default::default()
+        };
+        let snapper = Snapper::new(&settings, Vec2::new(64.0, 64.0), vec![]);
+        assert_eq!(
+            snapper.snap_point(Vec2::new(23.0, 25.0)),
+            Vec2::new(16.0, 32.0)
+        );
+
+        let disabled = Snapper::new(
+            &SnapSettings {
+                enabled: false,
+                ..settings
+            },
+            Vec2::new(64.0, 64.0),
+            vec![],
+        );
+        assert_eq!(
+            disabled.snap_point(Vec2::new(23.0, 70.0)),
+            Vec2::new(23.0, 64.0)
+        );
+    }
+}
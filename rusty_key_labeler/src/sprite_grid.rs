@@ -0,0 +1,211 @@
+use bevy::{
+    asset::AssetId,
+    image::Image,
+    math::{Rect, Vec2},
+    prelude::Resource,
+};
+use serde::{Deserialize, Serialize};
+
+/// A uniform layout of frames on a sprite sheet, in image pixels.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct SpriteGrid {
+    pub columns: u32,
+    pub rows: u32,
+    pub cell_width: f32,
+    pub cell_height: f32,
+    /// Offset of the first cell from the image's top-left corner.
+    pub margin_x: f32,
+    pub margin_y: f32,
+    /// Gap between neighbouring cells.
+    pub spacing_x: f32,
+    pub spacing_y: f32,
+}
+
+impl SpriteGrid {
+    pub fn cell_rect(&self, column: u32, row: u32) -> Rect {
+        let min = Vec2::new(
+            self.margin_x + column as f32 * (self.cell_width + self.spacing_x),
+            self.margin_y + row as f32 * (self.cell_height + self.spacing_y),
+        );
+
+        Rect::from_corners(min, min + Vec2::new(self.cell_width, self.cell_height))
+    }
+
+    /// Detects the grid from the runs of columns and rows containing
+    /// pixels with alpha above `alpha_threshold`. Frames packed with no
+    /// spacing form a single run, which is split using `frame_size` when it
+    /// is given for that axis.
+    pub fn detect(
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        alpha_threshold: u8,
+        frame_size: (Option<u32>, Option<u32>),
+    ) -> Option<Self> {
+        let opaque = |x: u32, y: u32| rgba[((y * width + x) * 4 + 3) as usize] > alpha_threshold;
+
+        let column_runs = occupied_runs((0..width).map(|x| (0..height).any(|y| opaque(x, y))));
+        let row_runs = occupied_runs((0..height).map(|y| (0..width).any(|x| opaque(x, y))));
+
+        let (columns, margin_x, cell_width, spacing_x) = axis_layout(&column_runs, frame_size.0)?;
+        let (rows, margin_y, cell_height, spacing_y) = axis_layout(&row_runs, frame_size.1)?;
+
+        Some(Self {
+            columns,
+            rows,
+            cell_width,
+            cell_height,
+            margin_x,
+            margin_y,
+            spacing_x,
+            spacing_y,
+        })
+    }
+}
+
+/// Start and end (exclusive) of each run of occupied positions.
+fn occupied_runs(occupied: impl Iterator<Item = bool>) -> Vec<(u32, u32)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    let mut end = 0;
+
+    for (position, occupied) in occupied.enumerate() {
+        let position = position as u32;
+        match (occupied, start) {
+            (true, None) => start = Some(position),
+            (false, Some(run_start)) => {
+                runs.push((run_start, position));
+                start = None;
+            }
+            _ => {}
+        }
+        end = position + 1;
+    }
+
+    if let Some(run_start) = start {
+        runs.push((run_start, end));
+    }
+
+    runs
+}
+
+/// Cell count, margin, cell size and spacing along one axis. Frames rarely
+/// fill their cell, so the cell is the widest run and the pitch the mean run
+/// distance. A run longer than `frame` holds touching frames, so the axis is
+/// split into back to back cells of that size instead.
+fn axis_layout(runs: &[(u32, u32)], frame: Option<u32>) -> Option<(u32, f32, f32, f32)> {
+    let (first_start, _) = *runs.first()?;
+    let (last_start, last_end) = *runs.last()?;
+
+    let widest = runs.iter().map(|(start, end)| end - start).max()?;
+    if let Some(frame) = frame.filter(|frame| *frame > 0 && widest > *frame) {
+        // Frames may not reach the left of their cell, so the margin is
+        // taken as the offset of the first run within its cell.
+        let margin = first_start % frame;
+        let count = (last_end - margin).div_ceil(frame);
+        return Some((count, margin as f32, frame as f32, 0.0));
+    }
+
+    let cell = widest as f32;
+    let pitch = match runs.len() {
+        1 => cell,
+        len => (last_start - first_start) as f32 / (len - 1) as f32,
+    };
+
+    Some((
+        runs.len() as u32,
+        first_start as f32,
+        cell,
+        (pitch - cell).max(0.0),
+    ))
+}
+
+/// Whether any pixel inside `rect` has alpha above `alpha_threshold`.
+pub fn has_content(rgba: &[u8], width: u32, height: u32, rect: Rect, alpha_threshold: u8) -> bool {
+    let rect = rect.intersect(Rect::new(0.0, 0.0, width as f32, height as f32));
+    let (min_x, min_y) = (rect.min.x.floor() as u32, rect.min.y.floor() as u32);
+    let (max_x, max_y) = (rect.max.x.ceil() as u32, rect.max.y.ceil() as u32);
+
+    (min_y..max_y)
+        .any(|y| (min_x..max_x).any(|x| rgba[((y * width + x) * 4 + 3) as usize] > alpha_threshold))
+}
+
+/// The grid drawn over the image it was detected on, awaiting bulk labeling.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct SpriteGridPreview {
+    pub image: Option<AssetId<Image>>,
+    pub grid: Option<SpriteGrid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_uniform_grid() {
+        // Three 4x4 frames per row with a 2 pixel margin and spacing; the
+        // middle frame of the second row is only partly filled.
+        let (width, height) = (20, 14);
+        let mut rgba = vec![0; (width * height * 4) as usize];
+        for row in 0..2 {
+            for column in 0..3 {
+                let frame_width = if (column, row) == (1, 1) { 2 } else { 4 };
+                for y in 0..4 {
+                    for x in 0..frame_width {
+                        let (px, py) = (2 + column * 6 + x, 2 + row * 6 + y);
+                        rgba[((py * width + px) * 4 + 3) as usize] = 255;
+                    }
+                }
+            }
+        }
+
+        let grid = SpriteGrid::detect(&rgba, width, height, 0, (None, None)).unwrap();
+        assert_eq!(
+            grid,
+            SpriteGrid {
+                columns: 3,
+                rows: 2,
+                cell_width: 4.0,
+                cell_height: 4.0,
+                margin_x: 2.0,
+                margin_y: 2.0,
+                spacing_x: 2.0,
+                spacing_y: 2.0,
+            }
+        );
+        assert_eq!(grid.cell_rect(2, 1), Rect::new(14.0, 8.0, 18.0, 12.0));
+        assert!(has_content(&rgba, width, height, grid.cell_rect(1, 1), 0));
+    }
+
+    #[test]
+    fn test_splits_touching_frames_by_frame_size() {
+        // Four 4x3 frames in one row with no margin or spacing; the last
+        // frame leaves its rightmost column empty.
+        let (width, height) = (16, 3);
+        let mut rgba = vec![0; (width * height * 4) as usize];
+        for y in 0..height {
+            for x in 0..15 {
+                rgba[((y * width + x) * 4 + 3) as usize] = 255;
+            }
+        }
+
+        let undivided = SpriteGrid::detect(&rgba, width, height, 0, (None, None)).unwrap();
+        assert_eq!((undivided.columns, undivided.rows), (1, 1));
+
+        let grid = SpriteGrid::detect(&rgba, width, height, 0, (Some(4), Some(3))).unwrap();
+        assert_eq!(
+            grid,
+            SpriteGrid {
+                columns: 4,
+                rows: 1,
+                cell_width: 4.0,
+                cell_height: 3.0,
+                margin_x: 0.0,
+                margin_y: 0.0,
+                spacing_x: 0.0,
+                spacing_y: 0.0,
+            }
+        );
+        assert_eq!(grid.cell_rect(3, 0), Rect::new(12.0, 0.0, 16.0, 3.0));
+    }
+}
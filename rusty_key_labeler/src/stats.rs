@@ -0,0 +1,365 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use bevy::math::Vec2;
+use hashbrown::HashMap;
+use serde::Serialize;
+use yolo_io::{PairingError, PairingResult, YoloFileParseError, YoloProject};
+
+use crate::resources::Config;
+
+pub const STATS_JSON_FILE_NAME: &str = "stats.json";
+pub const STATS_MARKDOWN_FILE_NAME: &str = "stats.md";
+
+/// Box sizes, the square root of a box's area, in image pixels.
+const SIZE_EDGES: [f32; 6] = [8.0, 16.0, 32.0, 64.0, 128.0, 256.0];
+/// Box width over height.
+const ASPECT_EDGES: [f32; 6] = [0.25, 0.5, 0.8, 1.25, 2.0, 4.0];
+const MAX_BOXES_PER_IMAGE_BUCKET: usize = 10;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Bucket {
+    pub label: String,
+    pub count: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Histogram {
+    pub buckets: Vec<Bucket>,
+}
+
+impl Histogram {
+    /// Buckets below the first edge, between each pair of edges and above the last.
+    fn with_edges(edges: &[f32]) -> Self {
+        let mut labels = vec![format!("<{}", edges[0])];
+        labels.extend(
+            edges
+                .windows(2)
+                .map(|pair| format!("{}-{}", pair[0], pair[1])),
+        );
+        labels.push(format!("{}+", edges[edges.len() - 1]));
+
+        Self::with_labels(labels)
+    }
+
+    fn with_labels(labels: Vec<String>) -> Self {
+        Self {
+            buckets: labels
+                .into_iter()
+                .map(|label| Bucket { label, count: 0 })
+                .collect(),
+        }
+    }
+
+    fn add(&mut self, bucket: usize) {
+        let last = self.buckets.len() - 1;
+        self.buckets[bucket.min(last)].count += 1;
+    }
+
+    fn add_value(&mut self, edges: &[f32], value: f32) {
+        self.add(edges.iter().take_while(|edge| value >= **edge).count());
+    }
+
+    pub fn max_count(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.count)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ClassStats {
+    pub class: isize,
+    pub name: String,
+    pub images: usize,
+    pub boxes: usize,
+}
+
+/// Label statistics across every image in the project.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct DatasetStats {
+    pub images: usize,
+    /// Images without a label file, with an empty one, or with no boxes.
+    pub unlabeled_images: usize,
+    pub boxes: usize,
+    pub classes: Vec<ClassStats>,
+    pub box_sizes: Histogram,
+    pub aspect_ratios: Histogram,
+    pub boxes_per_image: Histogram,
+    /// Boxes left out of the size and aspect histograms because their
+    /// image's dimensions could not be read.
+    pub unsized_boxes: usize,
+}
+
+impl DatasetStats {
+    pub fn new(
+        results: &[PairingResult],
+        class_map: &HashMap<isize, String>,
+        mut image_size: impl FnMut(&Path) -> Option<Vec2>,
+    ) -> Self {
+        let mut classes = class_map
+            .keys()
+            .map(|class| (*class, (0, 0)))
+            .collect::<BTreeMap<isize, (usize, usize)>>();
+
+        let mut stats = Self {
+            images: 0,
+            unlabeled_images: 0,
+            boxes: 0,
+            classes: vec![],
+            box_sizes: Histogram::with_edges(&SIZE_EDGES),
+            aspect_ratios: Histogram::with_edges(&ASPECT_EDGES),
+            boxes_per_image: Histogram::with_labels(
+                (0..MAX_BOXES_PER_IMAGE_BUCKET)
+                    .map(|count| count.to_string())
+                    .chain([format!("{}+", MAX_BOXES_PER_IMAGE_BUCKET)])
+                    .collect(),
+            ),
+            unsized_boxes: 0,
+        };
+
+        for result in results {
+            let pair = match result {
+                PairingResult::Valid(pair) => pair,
+                PairingResult::Invalid(PairingError::LabelFileMissing(_))
+                | PairingResult::Invalid(PairingError::LabelFileError(
+                    YoloFileParseError::EmptyFile(_),
+                )) => {
+                    stats.images += 1;
+                    stats.unlabeled_images += 1;
+                    stats.boxes_per_image.add(0);
+                    continue;
+                }
+                PairingResult::Invalid(_) => continue,
+            };
+
+            let entries = match &pair.label_file {
+                Some(label_file) => label_file.entries.as_slice(),
+                None => &[],
+            };
+
+            stats.images += 1;
+            stats.boxes += entries.len();
+            stats.boxes_per_image.add(entries.len());
+            if entries.is_empty() {
+                stats.unlabeled_images += 1;
+            }
+
+            let mut image_classes = entries
+                .iter()
+                .map(|entry| entry.class)
+                .collect::<Vec<isize>>();
+            image_classes.sort();
+            image_classes.dedup();
+            for class in image_classes {
+                classes.entry(class).or_default().0 += 1;
+            }
+
+            let size = match entries.is_empty() {
+                true => None,
+                false => pair.image_path.as_deref().and_then(&mut image_size),
+            };
+
+            for entry in entries {
+                classes.entry(entry.class).or_default().1 += 1;
+
+                match size {
+                    Some(size) => {
+                        let (width, height) = (entry.width * size.x, entry.height * size.y);
+                        stats
+                            .box_sizes
+                            .add_value(&SIZE_EDGES, (width * height).sqrt());
+                        if height > 0.0 {
+                            stats.aspect_ratios.add_value(&ASPECT_EDGES, width / height);
+                        }
+                    }
+                    None => stats.unsized_boxes += 1,
+                }
+            }
+        }
+
+        stats.classes = classes
+            .into_iter()
+            .map(|(class, (images, boxes))| ClassStats {
+                class,
+                name: class_map
+                    .get(&class)
+                    .cloned()
+                    .unwrap_or_else(|| class.to_string()),
+                images,
+                boxes,
+            })
+            .collect();
+
+        stats
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![
+            "# Dataset statistics".to_string(),
+            String::new(),
+            format!(
+                "{} images, {} unlabeled, {} boxes",
+                self.images, self.unlabeled_images, self.boxes
+            ),
+            String::new(),
+            "| Class | Images | Boxes |".to_string(),
+            "|---|---:|---:|".to_string(),
+        ];
+
+        for class in self.classes.iter() {
+            lines.push(format!(
+                "| {} ({}) | {} | {} |",
+                class.name, class.class, class.images, class.boxes
+            ));
+        }
+
+        for (title, histogram) in [
+            ("Box size (pixels)", &self.box_sizes),
+            ("Aspect ratio (width / height)", &self.aspect_ratios),
+            ("Boxes per image", &self.boxes_per_image),
+        ] {
+            lines.extend([
+                String::new(),
+                format!("## {}", title),
+                String::new(),
+                "| Bucket | Count |".to_string(),
+                "|---|---:|".to_string(),
+            ]);
+            lines.extend(
+                histogram
+                    .buckets
+                    .iter()
+                    .map(|bucket| format!("| {} | {} |", bucket.label, bucket.count)),
+            );
+        }
+
+        if self.unsized_boxes > 0 {
+            lines.push(String::new());
+            lines.push(format!(
+                "{} boxes are missing from the size histograms because their image could not be read.",
+                self.unsized_boxes
+            ));
+        }
+
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+/// Image sizes already read, so refreshing the stats panel stays cheap.
+pub type ImageSizeCache = HashMap<PathBuf, Option<Vec2>>;
+
+/// Image dimensions read from the file header, without decoding the pixels.
+pub fn read_image_size(path: &Path) -> Option<Vec2> {
+    image::image_dimensions(path)
+        .ok()
+        .map(|(width, height)| Vec2::new(width as f32, height as f32))
+}
+
+/// Computes the project's statistics and writes them as JSON and Markdown
+/// into `output_dir`.
+pub fn write_stats_report(
+    config: &Config,
+    output_dir: &Path,
+) -> Result<DatasetStats, Box<dyn Error>> {
+    let project = YoloProject::new(&config.project_config)?;
+    let stats = DatasetStats::new(
+        &project.data.pairs,
+        &config.project_config.export.class_map,
+        read_image_size,
+    );
+
+    std::fs::create_dir_all(output_dir)?;
+    std::fs::write(
+        output_dir.join(STATS_JSON_FILE_NAME),
+        serde_json::to_string_pretty(&stats)?,
+    )?;
+    std::fs::write(
+        output_dir.join(STATS_MARKDOWN_FILE_NAME),
+        stats.to_markdown(),
+    )?;
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use yolo_io::{ImageLabelPair, YoloEntry, YoloFile};
+
+    use super::*;
+
+    fn pair(name: &str, entries: Vec<YoloEntry>) -> PairingResult {
+        PairingResult::Valid(ImageLabelPair {
+            name: name.to_string(),
+            image_path: Some(PathBuf::from(format!("{}.png", name))),
+            label_file: Some(YoloFile {
+                stem: name.to_string(),
+                path: format!("{}.txt", name),
+                entries,
+            }),
+        })
+    }
+
+    fn entry(class: isize, width: f32, height: f32) -> YoloEntry {
+        YoloEntry {
+            class,
+            x_center: 0.5,
+            y_center: 0.5,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_aggregates_classes_and_histograms() {
+        let results = vec![
+            pair("a", vec![entry(0, 0.1, 0.1), entry(0, 0.5, 0.1)]),
+            pair("b", vec![entry(1, 0.1, 0.1)]),
+            PairingResult::Invalid(PairingError::LabelFileMissing("c.png".to_string())),
+        ];
+        let class_map = HashMap::from([
+            (0, "horizontal".to_string()),
+            (1, "vertical".to_string()),
+            (2, "unused".to_string()),
+        ]);
+
+        let stats = DatasetStats::new(&results, &class_map, |path| {
+            (path == Path::new("a.png")).then_some(Vec2::new(100.0, 100.0))
+        });
+
+        assert_eq!(
+            (stats.images, stats.unlabeled_images, stats.boxes),
+            (3, 1, 3)
+        );
+        assert_eq!(
+            stats
+                .classes
+                .iter()
+                .map(|class| (class.class, class.images, class.boxes))
+                .collect::<Vec<_>>(),
+            vec![(0, 1, 2), (1, 1, 1), (2, 0, 0)]
+        );
+        // 10x10 and 50x10 pixels; image b could not be sized.
+        assert_eq!(stats.box_sizes.buckets[1].count, 1);
+        assert_eq!(stats.box_sizes.buckets[2].count, 1);
+        assert_eq!(stats.aspect_ratios.buckets[3].count, 1);
+        assert_eq!(stats.aspect_ratios.buckets[6].count, 1);
+        assert_eq!(stats.unsized_boxes, 1);
+        assert_eq!(
+            stats
+                .boxes_per_image
+                .buckets
+                .iter()
+                .take(3)
+                .map(|bucket| bucket.count)
+                .collect::<Vec<_>>(),
+            vec![1, 1, 1]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use bevy::{asset::AssetId, image::Image, math::Rect, prelude::Resource, tasks::Task};
+
+/// A candidate box in image pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxProposal {
+    pub rect: Rect,
+    /// Class predicted by a detector. Accepted proposals without one get the
+    /// active class.
+    pub class: Option<isize>,
+    pub confidence: Option<f32>,
+}
+
+impl BoxProposal {
+    pub fn from_rect(rect: Rect) -> Self {
+        Self {
+            rect,
+            class: None,
+            confidence: None,
+        }
+    }
+}
+
+/// Candidate boxes waiting to be accepted or rejected. The first one is the
+/// proposal currently under review.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct BoxProposals {
+    pub image: Option<AssetId<Image>>,
+    pub proposals: Vec<BoxProposal>,
+}
+
+impl BoxProposals {
+    pub fn is_pending(&self) -> bool {
+        !self.proposals.is_empty()
+    }
+}
+
+/// Suggestions still being computed, with the image they are for.
+#[derive(Default, Resource)]
+pub struct SuggestionTasks {
+    pub pending: Option<(AssetId<Image>, Task<Vec<Rect>>)>,
+}
+
+/// Bounding rects of 8-connected regions of pixels whose alpha is above
+/// `alpha_threshold`, merged while they are within `merge_gap` pixels of
+/// each other and returned in reading order.
+pub fn alpha_component_rects(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    alpha_threshold: u8,
+    merge_gap: u32,
+) -> Vec<Rect> {
+    let (width, height) = (width as usize, height as usize);
+    let opaque = |x: usize, y: usize| rgba[(y * width + x) * 4 + 3] > alpha_threshold;
+
+    let mut visited = vec![false; width * height];
+    let mut rects = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..width * height {
+        if visited[start] || !opaque(start % width, start / width) {
+            continue;
+        }
+
+        visited[start] = true;
+        stack.push(start);
+        let (mut min_x, mut min_y) = (start % width, start / width);
+        let (mut max_x, mut max_y) = (min_x, min_y);
+
+        while let Some(pixel) = stack.pop() {
+            let (x, y) = (pixel % width, pixel / width);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+
+            for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                    let neighbour = ny * width + nx;
+                    if !visited[neighbour] && opaque(nx, ny) {
+                        visited[neighbour] = true;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+
+        rects.push(Rect::new(
+            min_x as f32,
+            min_y as f32,
+            (max_x + 1) as f32,
+            (max_y + 1) as f32,
+        ));
+    }
+
+    let mut rects = merge_nearby_rects(rects, merge_gap as f32);
+    rects.sort_by(|a, b| {
+        a.min
+            .y
+            .total_cmp(&b.min.y)
+            .then(a.min.x.total_cmp(&b.min.x))
+    });
+
+    rects
+}
+
+/// Merges rects within `gap` of each other. Each pass sweeps the rects in
+/// order of their left edge and joins overlapping groups with union-find;
+/// passes repeat while merged rects reach new neighbours.
+fn merge_nearby_rects(mut rects: Vec<Rect>, gap: f32) -> Vec<Rect> {
+    loop {
+        rects.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+
+        let mut parents = (0..rects.len()).collect::<Vec<usize>>();
+        for i in 0..rects.len() {
+            for j in (i + 1)..rects.len() {
+                // Later rects only start further right.
+                if rects[j].min.x - rects[i].max.x > gap {
+                    break;
+                }
+
+                let gap_y = (rects[j].min.y - rects[i].max.y).max(rects[i].min.y - rects[j].max.y);
+                if gap_y <= gap {
+                    let (root_i, root_j) = (find(&mut parents, i), find(&mut parents, j));
+                    parents[root_j] = root_i;
+                }
+            }
+        }
+
+        let mut merged: Vec<Option<Rect>> = vec![None; rects.len()];
+        for (i, rect) in rects.iter().enumerate() {
+            let root = find(&mut parents, i);
+            merged[root] = Some(merged[root].map_or(*rect, |merged| merged.union(*rect)));
+        }
+
+        let merged = merged.into_iter().flatten().collect::<Vec<Rect>>();
+        if merged.len() == rects.len() {
+            return merged;
+        }
+        rects = merged;
+    }
+}
+
+fn find(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+/// Intersection over union of two rects.
+pub fn iou(a: Rect, b: Rect) -> f32 {
+    let intersection = a.intersect(b);
+    if intersection.is_empty() {
+        return 0.0;
+    }
+
+    let area = |rect: Rect| rect.width() * rect.height();
+    let intersection_area = area(intersection);
+
+    intersection_area / (area(a) + area(b) - intersection_area)
+}
+
+/// Whether `rect` overlaps any of `existing` by more IoU than `max_overlap`,
+/// i.e. would duplicate a box that is already there.
+pub fn overlaps_existing(rect: Rect, existing: &[Rect], max_overlap: f32) -> bool {
+    existing
+        .iter()
+        .any(|existing| iou(rect, *existing) > max_overlap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba_with_opaque(width: u32, height: u32, opaque: &[(u32, u32)]) -> Vec<u8> {
+        let mut rgba = vec![0; (width * height * 4) as usize];
+        for (x, y) in opaque {
+            rgba[((y * width + x) * 4 + 3) as usize] = 255;
+        }
+        rgba
+    }
+
+    #[test]
+    fn test_components_are_merged_by_gap() {
+        // Two diagonal pixels, one pixel two columns away and one far away.
+        let rgba = rgba_with_opaque(10, 4, &[(0, 0), (1, 1), (4, 1), (9, 3)]);
+
+        let separate = alpha_component_rects(&rgba, 10, 4, 0, 0);
+        assert_eq!(
+            separate,
+            vec![
+                Rect::new(0.0, 0.0, 2.0, 2.0),
+                Rect::new(4.0, 1.0, 5.0, 2.0),
+                Rect::new(9.0, 3.0, 10.0, 4.0),
+            ]
+        );
+
+        let merged = alpha_component_rects(&rgba, 10, 4, 0, 2);
+        assert_eq!(
+            merged,
+            vec![
+                Rect::new(0.0, 0.0, 5.0, 2.0),
+                Rect::new(9.0, 3.0, 10.0, 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merged_rects_merge_with_new_neighbours() {
+        // The first two merge into a rect reaching down to the third, which
+        // is more than the gap away from either of them alone.
+        let rects = vec![
+            Rect::new(0.0, 0.0, 1.0, 1.0),
+            Rect::new(10.0, 0.0, 11.0, 10.0),
+            Rect::new(0.0, 9.0, 1.0, 10.0),
+            Rect::new(2.0, 0.0, 9.0, 1.0),
+        ];
+
+        assert_eq!(
+            merge_nearby_rects(rects, 1.0),
+            vec![Rect::new(0.0, 0.0, 11.0, 10.0)]
+        );
+    }
+
+    #[test]
+    fn test_overlaps_existing_above_max_overlap() {
+        let existing = [Rect::new(0.0, 0.0, 4.0, 4.0)];
+
+        // IoU 0.5 exactly is still allowed.
+        assert!(!overlaps_existing(
+            Rect::new(0.0, 0.0, 4.0, 2.0),
+            &existing,
+            0.5
+        ));
+        assert!(overlaps_existing(
+            Rect::new(0.0, 0.0, 4.0, 3.0),
+            &existing,
+            0.5
+        ));
+        assert!(!overlaps_existing(Rect::new(0.0, 0.0, 4.0, 3.0), &[], 0.5));
+    }
+}
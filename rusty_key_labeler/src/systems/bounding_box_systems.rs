@@ -8,16 +8,24 @@ use itertools::Itertools;
 
 use crate::{
     bounding_boxes::{BoundingBox, BoundingBoxPainter, ContainsBoundingBoxes, SelectedBoundingBox},
-    resources::AppData,
+    image_cache::ImageCache,
+    resources::{AppData, PendingBoxFocus},
+    review::ReviewManifest,
     utils::create_image_from_color,
-    ImageReady, SelectedImage, Ui,
+    CenterInViewport, FocusInViewport, ImageReady, SelectAfterReload, SelectedImage, Ui,
 };
 
+use super::current_label_file;
+
+/// Smallest area zoomed to, so tiny and zero-area boxes stay in context.
+const MIN_FOCUS_SIZE: f32 = 32.0;
+
+#[allow(clippy::too_many_arguments)]
 pub fn load_bounding_boxes(
     mut commands: Commands,
     mut images: ResMut<Assets<Image>>,
     query: Query<
-        (Entity, &Sprite),
+        (Entity, &Sprite, Option<&SelectAfterReload>),
         (
             With<SelectedImage>,
             With<ImageReady>,
@@ -26,6 +34,8 @@ pub fn load_bounding_boxes(
     >,
     bb_painter: Res<BoundingBoxPainter>,
     app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    review_manifest: Res<ReviewManifest>,
     ui: Res<Ui>,
 ) {
     if query.iter().count() == 0 {
@@ -34,18 +44,13 @@ pub fn load_bounding_boxes(
 
     debug!("Painting bounding boxes");
 
-    let pair = match app_data.yolo_project.pair_at_index(app_data.index) {
-        Some(pair) => pair,
-        None => {
-            error!("Pair not found");
-            return;
-        }
-    };
+    let (selected_image_eid, sprite, select_after_reload) = query.single();
 
-    let yolo_file = match pair.label_file {
+    // Prefers the labels parsed alongside a prefetched image.
+    let yolo_file = match current_label_file(&app_data, &image_cache, sprite) {
         Some(file) => file,
         None => {
-            error!("Label file not found");
+            error!("Pair not found");
             return;
         }
     };
@@ -53,8 +58,6 @@ pub fn load_bounding_boxes(
     let mut children = Vec::new();
     let mut ui_items = Vec::new();
 
-    let (selected_image_eid, sprite) = query.single();
-
     debug!("Selected image: {:?}", sprite.image.id());
 
     match images.get_mut(&sprite.image) {
@@ -83,6 +86,10 @@ pub fn load_bounding_boxes(
                 let bounding_box_id =
                     bb_painter.spawn_bounding_box(&mut commands, index, entry, image_size);
 
+                if select_after_reload.is_some_and(|select| select.0 == index) {
+                    commands.entity(bounding_box_id).insert(SelectedBoundingBox);
+                }
+
                 children.push(bounding_box_id);
 
                 let color = bb_painter.get_color(entry.class);
@@ -91,10 +98,14 @@ pub fn load_bounding_boxes(
                 let image = create_image_from_color(color, 40, 40);
                 let image_handle = images.add(image);
 
-                let item = ui.create_bounding_box_entry(
-                    &app_data.yolo_project.config.export.class_map[&entry.class],
-                    image_handle,
-                );
+                let class_name = &app_data.yolo_project.config.export.class_map[&entry.class];
+                let item = match review_manifest.box_review(&yolo_file.stem, index) {
+                    Some(box_review) => ui.create_bounding_box_entry(
+                        &format!("{}  ({})", class_name, box_review.describe()),
+                        image_handle,
+                    ),
+                    None => ui.create_bounding_box_entry(class_name, image_handle),
+                };
 
                 ui_items.push(item);
             }
@@ -113,12 +124,70 @@ pub fn load_bounding_boxes(
             return;
         }
     };
+    if select_after_reload.is_some() {
+        commands
+            .entity(selected_image_eid)
+            .remove::<SelectAfterReload>();
+    }
+
     if !children.is_empty() {
         debug!("Adding children to selected image");
         commands.entity(selected_image_eid).add_children(&children);
     }
 }
 
+/// Selects and zooms to the box waiting in `PendingBoxFocus` once its
+/// image's boxes are loaded.
+pub fn focus_pending_box(
+    mut commands: Commands,
+    app_data: Res<AppData>,
+    mut pending_box_focus: ResMut<PendingBoxFocus>,
+    bounding_boxes: Query<(Entity, &BoundingBox)>,
+    selected_bounding_boxes: Query<Entity, With<SelectedBoundingBox>>,
+    loaded_images: Query<(), (With<SelectedImage>, With<ContainsBoundingBoxes>)>,
+) {
+    let (pair_index, entry) = match pending_box_focus.0 {
+        Some(pending_focus) => pending_focus,
+        None => return,
+    };
+
+    if app_data.index as usize != pair_index {
+        return;
+    }
+
+    let (bounding_box_eid, bounding_box) = match bounding_boxes
+        .iter()
+        .find(|(_, bounding_box)| bounding_box.index == entry)
+    {
+        Some(bounding_box) => bounding_box,
+        // Waits for the boxes to load, then gives up on a missing entry.
+        None => {
+            if !loaded_images.is_empty() {
+                warn!("Bounding box {} no longer exists", entry);
+                pending_box_focus.0 = None;
+            }
+            return;
+        }
+    };
+
+    for selected_bounding_box_eid in selected_bounding_boxes.iter() {
+        commands
+            .entity(selected_bounding_box_eid)
+            .remove::<SelectedBoundingBox>();
+    }
+
+    commands
+        .entity(bounding_box_eid)
+        .insert(SelectedBoundingBox)
+        .insert(FocusInViewport {
+            width: bounding_box.width.max(MIN_FOCUS_SIZE),
+            height: bounding_box.height.max(MIN_FOCUS_SIZE),
+        })
+        .insert(CenterInViewport);
+
+    pending_box_focus.0 = None;
+}
+
 #[derive(Debug, Component, PartialEq)]
 pub struct CornerHandles {
     pub top_left: Vec2,
@@ -0,0 +1,250 @@
+use bevy::{
+    color::palettes::tailwind::{AMBER_500, GREEN_500},
+    prelude::*,
+    tasks::{block_on, futures_lite::future::poll_once, AsyncComputeTaskPool},
+};
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bounding_boxes::ContainsBoundingBoxes,
+    comparison::{Comparison, DisagreementRanking, ImageComparison},
+    filters::NavigationFilter,
+    image_cache::ImageCache,
+    key_bindings::KeyInput,
+    labels::entry_pixel_rect,
+    predictions::{PredictedEntry, Predictions},
+    resources::AppData,
+    settings::MAIN_LAYER,
+    utils::image_pixel_to_world,
+    ComparisonSummary, MainCamera, SelectedImage,
+};
+
+use super::{current_label_file, replace_selected_image};
+
+const COMPARISON_Z: f32 = 420.0;
+
+/// Run condition for `compare_labels`: the selected image's boxes were
+/// spawned, which follows a new image and every label edit, or the visible
+/// predictions changed.
+pub fn comparison_inputs_changed(
+    predictions: Res<Predictions>,
+    loaded_image: Query<(), (With<SelectedImage>, Added<ContainsBoundingBoxes>)>,
+) -> bool {
+    predictions.is_changed() || !loaded_image.is_empty()
+}
+
+/// Matches the selected image's labels against its visible predictions.
+pub fn compare_labels(
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    predictions: Res<Predictions>,
+    mut image_comparison: ResMut<ImageComparison>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+) {
+    if app_data.config.settings.predictions.labels.is_none() {
+        return;
+    }
+
+    let ground_truth = selected_image
+        .iter()
+        .next()
+        .and_then(|sprite| current_label_file(&app_data, &image_cache, sprite))
+        .map(|label_file| label_file.entries)
+        .unwrap_or_default();
+
+    let visible = predictions
+        .visible()
+        .cloned()
+        .collect::<Vec<PredictedEntry>>();
+
+    let comparison = Comparison::new(
+        &ground_truth,
+        &visible,
+        app_data.config.settings.predictions.match_iou,
+    );
+
+    let updated = ImageComparison {
+        ground_truth,
+        predictions: visible,
+        comparison,
+    };
+
+    if *image_comparison != updated {
+        *image_comparison = updated;
+    }
+}
+
+/// Highlights matched predictions and outlines missed ground truth boxes.
+pub fn draw_comparison(
+    mut painter: ShapePainter,
+    image_comparison: Res<ImageComparison>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+) {
+    let image_size = match selected_image
+        .iter()
+        .next()
+        .and_then(|sprite| images.get(&sprite.image))
+    {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.hollow = true;
+
+    let comparison = &image_comparison.comparison;
+
+    painter.thickness = 2.0;
+    painter.color = Color::from(GREEN_500);
+    for (_, prediction_index, _) in comparison.matches.iter() {
+        let entry = &image_comparison.predictions[*prediction_index].entry;
+        let rect = entry_pixel_rect(entry, image_size);
+        painter
+            .set_translation(image_pixel_to_world(rect.center(), image_size).extend(COMPARISON_Z));
+        painter.rect(rect.size());
+    }
+
+    painter.thickness = 3.0;
+    painter.color = Color::from(AMBER_500);
+    for truth_index in comparison.false_negatives.iter() {
+        let entry = &image_comparison.ground_truth[*truth_index];
+        let rect = entry_pixel_rect(entry, image_size);
+        painter
+            .set_translation(image_pixel_to_world(rect.center(), image_size).extend(COMPARISON_Z));
+        painter.rect(rect.size());
+    }
+}
+
+pub fn update_comparison_summary(
+    app_data: Res<AppData>,
+    image_comparison: Res<ImageComparison>,
+    mut summary: Query<&mut Text, With<ComparisonSummary>>,
+) {
+    if !image_comparison.is_changed() {
+        return;
+    }
+
+    let class_map = &app_data.yolo_project.config.export.class_map;
+    let class_name = |class: isize| {
+        class_map
+            .get(&class)
+            .cloned()
+            .unwrap_or_else(|| class.to_string())
+    };
+
+    let comparison = &image_comparison.comparison;
+    let mut lines = vec![format!(
+        "matched: {}  FP: {}  FN: {}",
+        comparison.matches.len(),
+        comparison.false_positives.len(),
+        comparison.false_negatives.len()
+    )];
+
+    for prediction_index in comparison.false_positives.iter() {
+        let predicted = &image_comparison.predictions[*prediction_index];
+        lines.push(match predicted.confidence {
+            Some(confidence) => format!(
+                "FP {} ({:.2})",
+                class_name(predicted.entry.class),
+                confidence
+            ),
+            None => format!("FP {}", class_name(predicted.entry.class)),
+        });
+    }
+
+    for truth_index in comparison.false_negatives.iter() {
+        let truth = &image_comparison.ground_truth[*truth_index];
+        lines.push(format!("FN {}", class_name(truth.class)));
+    }
+
+    for mut text in summary.iter_mut() {
+        text.0 = lines.join("\n");
+    }
+}
+
+/// Steps through the filtered images from most to least disagreement between
+/// labels and predictions. The ranking reads every prediction file, so it is
+/// computed in the background and the first jump happens once it is ready.
+#[allow(clippy::too_many_arguments)]
+pub fn jump_to_disagreement(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    keyboard_input: KeyInput,
+    mut app_data: ResMut<AppData>,
+    predictions: Res<Predictions>,
+    navigation_filter: Res<NavigationFilter>,
+    mut ranking: ResMut<DisagreementRanking>,
+    query_selected_images: Query<Entity, With<SelectedImage>>,
+    mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+) {
+    let ranked = match ranking.bypass_change_detection().pending.as_mut() {
+        Some(task) => block_on(poll_once(task)),
+        None => None,
+    };
+
+    let pressed = keyboard_input.just_pressed(&app_data.config.settings.key_map.next_disagreement);
+    let ranked = match ranked {
+        Some(ranked) => {
+            *ranking = ranked;
+            true
+        }
+        None => false,
+    };
+
+    if !pressed && !ranked {
+        return;
+    }
+
+    let settings = &app_data.config.settings.predictions;
+    let directory = match &settings.labels {
+        Some(directory) => directory.clone(),
+        None => {
+            info!("No prediction labels configured");
+            return;
+        }
+    };
+
+    if !ranked && !ranking.is_current(predictions.threshold, &navigation_filter.indices) {
+        if ranking.pending.is_none() {
+            info!("Ranking images by disagreement");
+            let pairs = app_data.yolo_project.get_valid_pairs();
+            let indices = navigation_filter.indices.clone();
+            let (threshold, iou) = (predictions.threshold, settings.match_iou);
+            ranking.pending = Some(AsyncComputeTaskPool::get().spawn(async move {
+                DisagreementRanking::rank(&pairs, &indices, &directory, threshold, iou)
+            }));
+        }
+        return;
+    }
+
+    if ranking.order.is_empty() {
+        info!("Labels and predictions agree on every image");
+        return;
+    }
+
+    let rank = ranking.position % ranking.order.len();
+    let (index, disagreement) = ranking.order[rank];
+    ranking.position = rank + 1;
+
+    info!(
+        "Image {} of {} by disagreement ({} boxes)",
+        rank + 1,
+        ranking.order.len(),
+        disagreement
+    );
+
+    app_data.index = index as isize;
+    let index_label =
+        navigation_filter.index_label(index, &app_data.yolo_project.config.export.class_map);
+
+    replace_selected_image(
+        &mut commands,
+        &asset_server,
+        &app_data,
+        index_label,
+        &mut main_camera,
+        &query_selected_images,
+    );
+}
@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy_ui_views::VStack;
+
+use crate::{
+    bounding_boxes::{BoundingBox, BoundingBoxPainter, ContainsBoundingBoxes, SelectedBoundingBox},
+    config_watch::{merge_reloaded_config, read_config, ConfigWatch},
+    resources::AppData,
+    FilmstripUpdateNeeded, HelpUpdateNeeded, SelectAfterReload, SelectedImage, StatsUpdateNeeded,
+    Ui, UiStyle,
+};
+
+type UiStyleQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static UiStyle,
+        Option<&'static mut BackgroundColor>,
+        Option<&'static mut BorderColor>,
+        Option<&'static mut TextColor>,
+        Option<&'static mut TextFont>,
+    ),
+    With<Node>,
+>;
+
+/// Re-reads the config file when it changes and swaps it in place, keeping
+/// the current image and camera. A config that fails to parse or validate
+/// is reported and the running one is kept.
+#[allow(clippy::too_many_arguments)]
+pub fn reload_config(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut config_watch: ResMut<ConfigWatch>,
+    mut app_data: ResMut<AppData>,
+    mut bounding_box_painter: ResMut<BoundingBoxPainter>,
+    mut ui: ResMut<Ui>,
+    selected_image: Query<Entity, With<SelectedImage>>,
+    selected_bounding_box: Query<&BoundingBox, With<SelectedBoundingBox>>,
+    mut ui_styles: UiStyleQuery,
+    mut vstacks: Query<&mut VStack>,
+) {
+    if !config_watch.timer.tick(time.delta()).just_finished() || !config_watch.changed() {
+        return;
+    }
+
+    let path = config_watch.path.to_string_lossy().into_owned();
+    let config = match read_config(&path) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            error!("Not reloading {}: {}", path, e);
+            return;
+        }
+    };
+
+    let (config, notes) = match merge_reloaded_config(&app_data.config, config) {
+        Ok(merged) => merged,
+        Err(e) => {
+            error!("Not reloading {}: {}", path, e);
+            return;
+        }
+    };
+
+    for note in notes {
+        warn!("{}", note);
+    }
+
+    *bounding_box_painter = BoundingBoxPainter::new(
+        &config.settings.bounding_boxes,
+        &app_data.yolo_project.config.export.class_map,
+    );
+
+    let ui_panel = &config.settings.ui_panel;
+    let mut new_ui = Ui::new(&ui_panel.colors, ui_panel.font_size, &ui_panel.font_path);
+    new_ui.font_handle = match new_ui.font_path == ui.font_path {
+        true => ui.font_handle.clone(),
+        false => Some(asset_server.load(new_ui.font_path.clone())),
+    };
+    restyle_ui(&ui, &new_ui, &mut ui_styles, &mut vstacks);
+    *ui = new_ui;
+
+    app_data.config = config;
+    info!("Reloaded {}", path);
+
+    // Repaints boxes and the left panel with the new colors.
+    let select = selected_bounding_box
+        .iter()
+        .next()
+        .map(|bounding_box| bounding_box.index);
+    for selected_image_eid in selected_image.iter() {
+        let mut selected_image = commands.entity(selected_image_eid);
+        selected_image
+            .despawn_descendants()
+            .remove::<ContainsBoundingBoxes>();
+        if let Some(select) = select {
+            selected_image.insert(SelectAfterReload(select));
+        }
+    }
+
+    commands.spawn(StatsUpdateNeeded);
+    commands.spawn(HelpUpdateNeeded);
+    commands.spawn(FilmstripUpdateNeeded);
+}
+
+/// Restyles spawned UI nodes from the `UiColors` roles and font scale they
+/// were tagged with. Font sizes are recomputed from the configured size.
+fn restyle_ui(old: &Ui, new: &Ui, ui_styles: &mut UiStyleQuery, vstacks: &mut Query<&mut VStack>) {
+    if old.colors == new.colors
+        && old.font_size == new.font_size
+        && old.font_handle == new.font_handle
+    {
+        return;
+    }
+
+    for (style, background, border, text_color, text_font) in ui_styles.iter_mut() {
+        if let (Some(role), Some(mut background)) = (style.background, background) {
+            background.0 = role.color(&new.colors);
+        }
+
+        if let (Some(role), Some(mut border)) = (style.border, border) {
+            border.0 = role.color(&new.colors);
+        }
+
+        if let (Some(role), Some(mut text_color)) = (style.text, text_color) {
+            text_color.0 = role.color(&new.colors);
+        }
+
+        if let (Some(_), Some(mut text_font)) = (style.text, text_font) {
+            text_font.font_size = new.font_size * style.font_scale;
+            if let Some(font) = &new.font_handle {
+                text_font.font = font.clone();
+            }
+        }
+    }
+
+    for mut vstack in vstacks.iter_mut() {
+        vstack.background_color = new.colors.background;
+        vstack.border_color = new.colors.outer_border;
+    }
+}
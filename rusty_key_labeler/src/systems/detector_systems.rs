@@ -0,0 +1,240 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future::poll_once, AsyncComputeTaskPool},
+};
+
+use crate::{
+    bounding_boxes::BoundingBox,
+    detector::{run_detector, DetectorTasks},
+    image_cache::ImageCache,
+    key_bindings::KeyInput,
+    labels::pixel_rect_to_entry,
+    resources::AppData,
+    suggestions::{overlaps_existing, BoxProposal, BoxProposals},
+    ImageReady, LabelEditNeeded, MainCamera, SelectedImage,
+};
+
+use super::{center_on_current_proposal, current_label_file, default_class};
+
+/// Starts the detector on the current image when its key is pressed, or on
+/// every newly shown image when `auto_run` is set.
+pub fn request_detections(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut tasks: ResMut<DetectorTasks>,
+    ready_image: Query<&SelectedImage, Added<ImageReady>>,
+) {
+    let detector = &app_data.config.settings.detector;
+    let key_pressed = keyboard_input.just_pressed(&app_data.config.settings.key_map.run_detector);
+    let image_shown = detector.auto_run && !ready_image.is_empty();
+    if !key_pressed && !image_shown {
+        return;
+    }
+
+    let command = match &detector.command {
+        Some(command) => command.clone(),
+        None => {
+            if key_pressed {
+                info!("No detector command configured");
+            }
+            return;
+        }
+    };
+
+    let image_path = match app_data
+        .yolo_project
+        .pair_at_index(app_data.index)
+        .and_then(|pair| pair.image_path)
+    {
+        Some(image_path) => image_path,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    if tasks.pending.contains_key(&image_path) {
+        return;
+    }
+
+    info!("Running detector on {:?}", image_path);
+
+    let args = detector.args.clone();
+    let timeout = Duration::from_secs_f32(detector.timeout_seconds.max(0.0));
+    let task_path = image_path.clone();
+    let task = AsyncComputeTaskPool::get()
+        .spawn(async move { run_detector(&command, &args, &task_path, timeout) });
+
+    tasks.pending.insert(image_path, task);
+}
+
+/// Turns finished detections for the current image into pending proposals.
+#[allow(clippy::too_many_arguments)]
+pub fn receive_detections(
+    app_data: Res<AppData>,
+    images: Res<Assets<Image>>,
+    mut tasks: ResMut<DetectorTasks>,
+    mut proposals: ResMut<BoxProposals>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<&BoundingBox>,
+    mut main_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    let finished = tasks
+        .bypass_change_detection()
+        .pending
+        .iter_mut()
+        .filter_map(|(image_path, task)| {
+            block_on(poll_once(task)).map(|result| (image_path.clone(), result))
+        })
+        .collect::<Vec<_>>();
+
+    for (image_path, result) in finished {
+        tasks.pending.remove(&image_path);
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Detector failed on {:?}: {}", image_path, e);
+                continue;
+            }
+        };
+
+        // Detections for an image navigated away from are dropped.
+        let current_path = app_data
+            .yolo_project
+            .pair_at_index(app_data.index)
+            .and_then(|pair| pair.image_path);
+        if current_path.as_ref() != Some(&image_path) {
+            continue;
+        }
+
+        let (sprite, image_size) = match selected_image.iter().next().and_then(|sprite| {
+            images.get(&sprite.image).map(|image| {
+                (
+                    sprite,
+                    Vec2::new(image.width() as f32, image.height() as f32),
+                )
+            })
+        }) {
+            Some(selected) => selected,
+            None => continue,
+        };
+
+        let class_map = &app_data.yolo_project.config.export.class_map;
+        let max_overlap = app_data.config.settings.suggestions.max_overlap;
+        let existing = bounding_boxes
+            .iter()
+            .map(BoundingBox::pixel_rect)
+            .collect::<Vec<Rect>>();
+
+        let mut detected = response
+            .boxes
+            .iter()
+            .filter(|detected| {
+                let known = class_map.contains_key(&detected.class);
+                if !known {
+                    warn!("Detector returned unknown class {}", detected.class);
+                }
+                known
+            })
+            .map(|detected| BoxProposal {
+                rect: detected.pixel_rect(image_size),
+                class: Some(detected.class),
+                confidence: Some(detected.confidence),
+            })
+            .filter(|proposal| !overlaps_existing(proposal.rect, &existing, max_overlap))
+            .collect::<Vec<BoxProposal>>();
+
+        // Most confident first, so review starts with the likeliest boxes.
+        detected.sort_by(|a, b| {
+            b.confidence
+                .unwrap_or(0.0)
+                .total_cmp(&a.confidence.unwrap_or(0.0))
+        });
+
+        info!("Detector proposed {} boxes", detected.len());
+
+        *proposals = BoxProposals {
+            image: Some(sprite.image.id()),
+            proposals: detected,
+        };
+
+        center_on_current_proposal(&proposals, image_size, &mut main_camera);
+    }
+}
+
+/// Accepts every pending proposal at or above the detector's threshold.
+#[allow(clippy::too_many_arguments)]
+pub fn accept_confident_proposals(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    images: Res<Assets<Image>>,
+    mut proposals: ResMut<BoxProposals>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    mut main_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.accept_confident_proposals)
+        || !proposals.is_pending()
+    {
+        return;
+    }
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    if proposals.image != Some(sprite.image.id()) {
+        return;
+    }
+
+    let image_size = match images.get(&sprite.image) {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let threshold = app_data.config.settings.detector.accept_threshold;
+    let (accepted, remaining): (Vec<BoxProposal>, Vec<BoxProposal>) =
+        proposals.proposals.iter().partition(|proposal| {
+            proposal
+                .confidence
+                .is_some_and(|confidence| confidence >= threshold)
+        });
+
+    if accepted.is_empty() {
+        info!("No proposals at or above confidence {}", threshold);
+        return;
+    }
+
+    let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+        Some(label_file) => label_file,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    info!("Accepted {} proposals", accepted.len());
+
+    label_file
+        .entries
+        .extend(accepted.into_iter().map(|proposal| {
+            pixel_rect_to_entry(
+                proposal.class.unwrap_or_else(|| default_class(&app_data)),
+                proposal.rect,
+                image_size,
+            )
+        }));
+
+    commands.spawn(LabelEditNeeded {
+        label_file,
+        select: None,
+    });
+
+    proposals.proposals = remaining;
+    center_on_current_proposal(&proposals, image_size, &mut main_camera);
+}
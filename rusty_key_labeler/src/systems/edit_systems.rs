@@ -0,0 +1,426 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_vector_shapes::prelude::*;
+use yolo_io::{PairingResult, YoloFile};
+
+use crate::{
+    bounding_boxes::{BoundingBox, ContainsBoundingBoxes, SelectedBoundingBox},
+    comparison::DisagreementRanking,
+    filters::NavigationFilter,
+    image_cache::ImageCache,
+    key_bindings::KeyInput,
+    labels::{pixel_rect_to_entry, write_label_file},
+    modal::{InputMode, ModalState},
+    resources::{AppData, BoxDrawing},
+    review::ReviewManifest,
+    settings::MAIN_LAYER,
+    snapping::Snapper,
+    thumbnails::ThumbnailCache,
+    utils::{image_pixel_to_world, world_to_image_pixel},
+    LabelEditNeeded, MainCamera, SelectAfterReload, SelectedImage, StatsUpdateNeeded, Ui,
+    UiLabelingIndexUpdateNeeded,
+};
+
+use super::save_review_manifest;
+
+const PREVIEW_Z: f32 = 600.0;
+
+/// The labels of the selected image, or an empty label file next to the
+/// project's labels when the image has none yet. The pair is found from the
+/// sprite's image rather than `app_data.index`, which runs ahead of the
+/// shown image while scrubbing.
+pub fn current_label_file(
+    app_data: &AppData,
+    image_cache: &ImageCache,
+    sprite: &Sprite,
+) -> Option<YoloFile> {
+    let image_id = sprite.image.id().untyped();
+    if let Some(label_file) = image_cache.label_file_for(image_id) {
+        return Some(label_file.clone());
+    }
+
+    let image_path = image_cache
+        .path_for(image_id)
+        .or_else(|| sprite.image.path().map(|asset_path| asset_path.path()))?;
+    let pair = app_data
+        .yolo_project
+        .get_valid_pairs()
+        .into_iter()
+        .find(|pair| pair.image_path.as_deref() == Some(image_path))?;
+
+    match pair.label_file {
+        Some(label_file) => Some(label_file),
+        None => Some(YoloFile {
+            stem: pair.name.clone(),
+            path: Path::new(&app_data.yolo_project.config.source_paths.labels)
+                .join(format!("{}.txt", pair.name))
+                .to_string_lossy()
+                .into_owned(),
+            entries: vec![],
+        }),
+    }
+}
+
+/// Class given to new boxes when no active class has been chosen.
+pub fn default_class(app_data: &AppData) -> isize {
+    app_data.active_class.unwrap_or_else(|| {
+        app_data
+            .yolo_project
+            .config
+            .export
+            .class_map
+            .keys()
+            .min()
+            .copied()
+            .unwrap_or(0)
+    })
+}
+
+fn snapper(
+    app_data: &AppData,
+    keyboard_input: &KeyInput,
+    image_size: Vec2,
+    others: Vec<Rect>,
+) -> Snapper {
+    let settings = &app_data.config.settings;
+    let mut snap_settings = settings.snapping.clone();
+    snap_settings.enabled &= !keyboard_input.pressed(&settings.key_map.disable_snapping);
+
+    Snapper::new(&snap_settings, image_size, others)
+}
+
+/// The selected image's sprite and size in pixels, once it has loaded.
+pub fn selected_image_size(
+    images: &Assets<Image>,
+    selected_image: &Query<&Sprite, With<SelectedImage>>,
+) -> Option<(Sprite, Vec2)> {
+    let sprite = selected_image.iter().next()?;
+    let image = images.get(&sprite.image)?;
+
+    Some((
+        sprite.clone(),
+        Vec2::new(image.width() as f32, image.height() as f32),
+    ))
+}
+
+/// The first press anchors a corner at the keyboard cursor, the second
+/// creates a box reaching to the cursor's new position.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_bounding_box(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    images: Res<Assets<Image>>,
+    mut drawing: ResMut<BoxDrawing>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<&BoundingBox>,
+    main_camera: Query<&Transform, With<MainCamera>>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+    if !keyboard_input.just_pressed_ignoring(&key_map.draw_box, &key_map.disable_snapping) {
+        return;
+    }
+
+    let (sprite, image_size) = match selected_image_size(&images, &selected_image) {
+        Some(selected) => selected,
+        None => return,
+    };
+
+    let others = bounding_boxes.iter().map(BoundingBox::pixel_rect).collect();
+    let snapper = snapper(&app_data, &keyboard_input, image_size, others);
+    let cursor = snapper.snap_point(world_to_image_pixel(
+        main_camera.single().translation.truncate(),
+        image_size,
+    ));
+
+    let anchor = match drawing.anchor.take() {
+        Some(anchor) => anchor,
+        None => {
+            drawing.anchor = Some(cursor);
+            return;
+        }
+    };
+
+    let rect = Rect::from_corners(anchor, cursor);
+    if rect.width() < 1.0 || rect.height() < 1.0 {
+        info!("Bounding box too small; drawing cancelled");
+        return;
+    }
+
+    let mut label_file = match current_label_file(&app_data, &image_cache, &sprite) {
+        Some(label_file) => label_file,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    label_file.entries.push(pixel_rect_to_entry(
+        default_class(&app_data),
+        rect,
+        image_size,
+    ));
+
+    commands.spawn(LabelEditNeeded {
+        select: Some(label_file.entries.len() - 1),
+        label_file,
+    });
+}
+
+/// Removes the selected box from its label file, along with its review marks.
+pub fn delete_bounding_box(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    mut review_manifest: ResMut<ReviewManifest>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    selected_bounding_box: Query<&BoundingBox, With<SelectedBoundingBox>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.delete_box) {
+        return;
+    }
+
+    let entry = match selected_bounding_box.iter().next() {
+        Some(bounding_box) => bounding_box.index,
+        None => {
+            info!("No bounding box selected to delete");
+            return;
+        }
+    };
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+        Some(label_file) => label_file,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    if entry >= label_file.entries.len() {
+        error!("Bounding box {} not found in {}", entry, label_file.path);
+        return;
+    }
+
+    label_file.entries.remove(entry);
+    if review_manifest.remove_boxes(&label_file.stem, &[entry]) {
+        save_review_manifest(&review_manifest, &app_data);
+    }
+
+    commands.spawn(LabelEditNeeded {
+        label_file,
+        select: None,
+    });
+}
+
+/// Moves the selected box's corner nearest the keyboard cursor onto it.
+#[allow(clippy::too_many_arguments)]
+pub fn move_bounding_box_corner(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<(&BoundingBox, Has<SelectedBoundingBox>)>,
+    main_camera: Query<&Transform, With<MainCamera>>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+    if !keyboard_input.just_pressed_ignoring(&key_map.move_corner, &key_map.disable_snapping) {
+        return;
+    }
+
+    let selected_box = match bounding_boxes.iter().find(|(_, selected)| *selected) {
+        Some((bounding_box, _)) => bounding_box,
+        None => {
+            info!("No bounding box selected to resize");
+            return;
+        }
+    };
+
+    let (sprite, image_size) = match selected_image_size(&images, &selected_image) {
+        Some(selected) => selected,
+        None => return,
+    };
+
+    let others = bounding_boxes
+        .iter()
+        .filter(|(_, selected)| !selected)
+        .map(|(bounding_box, _)| bounding_box.pixel_rect())
+        .collect();
+    let snapper = snapper(&app_data, &keyboard_input, image_size, others);
+    let cursor = snapper.snap_point(world_to_image_pixel(
+        main_camera.single().translation.truncate(),
+        image_size,
+    ));
+
+    // The corner diagonally opposite the one being moved stays put.
+    let rect = selected_box.pixel_rect();
+    let corners = [
+        (rect.min, rect.max),
+        (
+            Vec2::new(rect.max.x, rect.min.y),
+            Vec2::new(rect.min.x, rect.max.y),
+        ),
+        (
+            Vec2::new(rect.min.x, rect.max.y),
+            Vec2::new(rect.max.x, rect.min.y),
+        ),
+        (rect.max, rect.min),
+    ];
+    let fixed_corner = corners
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.distance(cursor).total_cmp(&b.distance(cursor)))
+        .map(|(_, opposite)| opposite)
+        .unwrap_or(rect.min);
+
+    let resized = Rect::from_corners(fixed_corner, cursor);
+    if resized.width() < 1.0 || resized.height() < 1.0 {
+        info!("Bounding box too small; resize cancelled");
+        return;
+    }
+
+    let mut label_file = match current_label_file(&app_data, &image_cache, &sprite) {
+        Some(label_file) => label_file,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    match label_file.entries.get_mut(selected_box.index) {
+        Some(entry) => *entry = pixel_rect_to_entry(entry.class, resized, image_size),
+        None => {
+            error!("Label entry {} not found", selected_box.index);
+            return;
+        }
+    }
+
+    commands.spawn(LabelEditNeeded {
+        label_file,
+        select: Some(selected_box.index),
+    });
+}
+
+/// Drops the anchored corner on `cancel_box`, or once insert mode is left
+/// when modes are enabled.
+pub fn cancel_box_drawing(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    modal_state: Res<ModalState>,
+    mut drawing: ResMut<BoxDrawing>,
+) {
+    if drawing.anchor.is_none() {
+        return;
+    }
+
+    let settings = &app_data.config.settings;
+    let left_insert_mode = settings.modal.enabled && modal_state.mode != InputMode::Insert;
+    if left_insert_mode || keyboard_input.just_pressed(&settings.key_map.cancel_box) {
+        drawing.anchor = None;
+        info!("Box drawing cancelled");
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_box_preview(
+    mut painter: ShapePainter,
+    drawing: Res<BoxDrawing>,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    ui: Res<Ui>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    main_camera: Query<&Transform, With<MainCamera>>,
+) {
+    let anchor = match drawing.anchor {
+        Some(anchor) => anchor,
+        None => return,
+    };
+
+    let image_size = match selected_image_size(&images, &selected_image) {
+        Some((_, image_size)) => image_size,
+        None => return,
+    };
+
+    let snapper = snapper(&app_data, &keyboard_input, image_size, vec![]);
+    let cursor = snapper.snap_point(world_to_image_pixel(
+        main_camera.single().translation.truncate(),
+        image_size,
+    ));
+    let rect = Rect::from_corners(anchor, cursor);
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.thickness = 1.0;
+    painter.hollow = true;
+    painter.color = ui.colors.inner_border;
+    painter.set_translation(image_pixel_to_world(rect.center(), image_size).extend(PREVIEW_Z));
+    painter.rect(rect.size());
+}
+
+/// Writes edited labels to disk, updates every in-memory copy of them and
+/// repaints the selected image's boxes.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_label_edits(
+    mut commands: Commands,
+    edits: Query<(Entity, &LabelEditNeeded)>,
+    mut app_data: ResMut<AppData>,
+    mut image_cache: ResMut<ImageCache>,
+    mut thumbnail_cache: ResMut<ThumbnailCache>,
+    mut navigation_filter: ResMut<NavigationFilter>,
+    mut disagreement_ranking: ResMut<DisagreementRanking>,
+    selected_image: Query<Entity, With<SelectedImage>>,
+) {
+    for (edit_eid, edit) in edits.iter() {
+        commands.entity(edit_eid).despawn();
+
+        if let Err(e) = write_label_file(&edit.label_file) {
+            error!("Failed to write label file {}: {}", edit.label_file.path, e);
+            continue;
+        }
+
+        let mut image_path = None;
+        for result in app_data.yolo_project.data.pairs.iter_mut() {
+            if let PairingResult::Valid(pair) = result {
+                if pair.name == edit.label_file.stem {
+                    pair.label_file = Some(edit.label_file.clone());
+                    image_path = pair.image_path.clone();
+                }
+            }
+        }
+
+        if let Some(image_path) = image_path {
+            image_cache.set_label_file(&image_path, Some(edit.label_file.clone()));
+            thumbnail_cache.invalidate(&image_path);
+        }
+
+        navigation_filter.rebuild(&app_data.yolo_project.get_valid_pairs());
+        disagreement_ranking.invalidate();
+        commands.spawn(StatsUpdateNeeded);
+        commands.spawn(UiLabelingIndexUpdateNeeded(navigation_filter.index_label(
+            app_data.index as usize,
+            &app_data.yolo_project.config.export.class_map,
+        )));
+
+        for selected_image_eid in selected_image.iter() {
+            commands
+                .entity(selected_image_eid)
+                .despawn_descendants()
+                .remove::<ContainsBoundingBoxes>();
+
+            if let Some(index) = edit.select {
+                commands
+                    .entity(selected_image_eid)
+                    .insert(SelectAfterReload(index));
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future::poll_once, AsyncComputeTaskPool},
+};
+use itertools::Itertools;
+
+use crate::{
+    bounding_boxes::BoundingBoxPainter,
+    filters::NavigationFilter,
+    resources::AppData,
+    review::ReviewManifest,
+    thumbnails::{thumbnail_to_bevy_image, ThumbnailBadge, ThumbnailCache, ThumbnailRequest},
+    FilmstripBadge, FilmstripThumbnail, FilmstripUpdateNeeded,
+};
+
+/// Queues thumbnails for the images around the current one and collects
+/// the ones finished by the background tasks.
+pub fn generate_thumbnails(
+    mut commands: Commands,
+    app_data: Res<AppData>,
+    navigation_filter: Res<NavigationFilter>,
+    bb_painter: Res<BoundingBoxPainter>,
+    mut thumbnail_cache: ResMut<ThumbnailCache>,
+    mut images: ResMut<Assets<Image>>,
+    mut last_index: Local<Option<isize>>,
+) {
+    let filmstrip = &app_data.config.settings.filmstrip;
+    if !filmstrip.enabled {
+        return;
+    }
+
+    let finished = thumbnail_cache
+        .bypass_change_detection()
+        .pending
+        .iter_mut()
+        .filter_map(|(image_path, task)| {
+            block_on(poll_once(task)).map(|thumbnail| (image_path.clone(), thumbnail))
+        })
+        .collect::<Vec<_>>();
+
+    let mut update_needed = !finished.is_empty();
+
+    for (image_path, thumbnail) in finished {
+        thumbnail_cache.pending.remove(&image_path);
+        match thumbnail {
+            Some(thumbnail) => {
+                let handle = images.add(thumbnail_to_bevy_image(thumbnail));
+                thumbnail_cache.thumbnails.insert(image_path, handle);
+            }
+            None => {
+                warn!("Unable to create thumbnail for {:?}", image_path);
+                thumbnail_cache.failed.insert(image_path);
+            }
+        }
+    }
+
+    // Edited images lose their thumbnails, so their slots are queued again.
+    if *last_index != Some(app_data.index) || thumbnail_cache.invalidated {
+        *last_index = Some(app_data.index);
+        thumbnail_cache.invalidated = false;
+        update_needed = true;
+
+        let valid_pairs = app_data.yolo_project.get_valid_pairs();
+        let neighbours = filmstrip.neighbours as isize;
+        let task_pool = AsyncComputeTaskPool::get();
+
+        // Nearest images first, so the slots next to the current one fill in first.
+        let offsets = (0..=neighbours).flat_map(|distance| [distance, -distance]);
+        let window = offsets
+            .filter_map(|offset| filmstrip_index(&navigation_filter, app_data.index, offset))
+            .unique()
+            .collect::<Vec<usize>>();
+
+        thumbnail_cache.retain(
+            &window
+                .iter()
+                .filter_map(|index| valid_pairs[*index].image_path.clone())
+                .collect(),
+        );
+
+        for index in window {
+            let request = match ThumbnailRequest::new(
+                &valid_pairs[index],
+                &thumbnail_cache.directory,
+                &bb_painter.bounding_box_settings.class_color_map,
+                thumbnail_cache.size,
+            ) {
+                Some(request) => request,
+                None => continue,
+            };
+
+            if thumbnail_cache.is_known(&request.image_path) {
+                continue;
+            }
+
+            let image_path = request.image_path.clone();
+            let task = task_pool.spawn(async move { request.load_or_generate() });
+            thumbnail_cache.pending.insert(image_path, task);
+        }
+    }
+
+    if update_needed {
+        commands.spawn(FilmstripUpdateNeeded);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_filmstrip(
+    mut commands: Commands,
+    update_query: Query<Entity, With<FilmstripUpdateNeeded>>,
+    app_data: Res<AppData>,
+    navigation_filter: Res<NavigationFilter>,
+    thumbnail_cache: Res<ThumbnailCache>,
+    review_manifest: Res<ReviewManifest>,
+    mut thumbnails: Query<(&FilmstripThumbnail, &mut ImageNode)>,
+    mut badges: Query<(&FilmstripBadge, &mut Text)>,
+) {
+    if update_query.iter().count() == 0 {
+        return;
+    }
+
+    for update_eid in update_query.iter() {
+        commands.entity(update_eid).despawn();
+    }
+
+    let valid_pairs = app_data.yolo_project.get_valid_pairs();
+
+    for (slot, mut image_node) in thumbnails.iter_mut() {
+        image_node.image = filmstrip_index(&navigation_filter, app_data.index, slot.offset)
+            .and_then(|index| valid_pairs[index].image_path.as_ref())
+            .and_then(|image_path| thumbnail_cache.thumbnails.get(image_path))
+            .cloned()
+            .unwrap_or_else(|| ImageNode::default().image);
+    }
+
+    for (slot, mut text) in badges.iter_mut() {
+        text.0 = match filmstrip_index(&navigation_filter, app_data.index, slot.offset) {
+            Some(index) => ThumbnailBadge::for_pair(
+                &valid_pairs[index],
+                thumbnail_cache.started_at,
+                review_manifest.status(&valid_pairs[index].name),
+            )
+            .iter()
+            .map(|badge| badge.symbol())
+            .join(" "),
+            None => String::new(),
+        };
+    }
+}
+
+/// The pair index shown `offset` slots away from the current image.
+fn filmstrip_index(
+    navigation_filter: &NavigationFilter,
+    current_index: isize,
+    offset: isize,
+) -> Option<usize> {
+    match offset {
+        0 => Some(current_index as usize),
+        _ => navigation_filter.step(current_index as usize, offset),
+    }
+}
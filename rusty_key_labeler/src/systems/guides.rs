@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bounding_boxes::{BoundingBox, SelectedBoundingBox},
+    key_bindings::KeyInput,
+    predictions::Predictions,
+    resources::{AppData, GuideOverlays},
+    settings::MAIN_LAYER,
+    utils::world_to_image_pixel,
+    ComputedViewport, CursorReadout, MainCamera, SelectedImage, Ui,
+};
+
+const GUIDE_Z: f32 = 500.0;
+
+pub fn toggle_guides(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut overlays: ResMut<GuideOverlays>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+
+    if keyboard_input.just_pressed(&key_map.toggle_pixel_grid) {
+        overlays.pixel_grid = !overlays.pixel_grid;
+    }
+
+    if keyboard_input.just_pressed(&key_map.toggle_crosshair) {
+        overlays.crosshair = !overlays.crosshair;
+    }
+}
+
+/// Draws the pixel grid and the crosshair through the keyboard cursor, which
+/// is the camera center that the pan keys move.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_guides(
+    mut painter: ShapePainter,
+    overlays: Res<GuideOverlays>,
+    app_data: Res<AppData>,
+    ui: Res<Ui>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    main_camera: Query<(&OrthographicProjection, &Transform), With<MainCamera>>,
+    computed_viewport: Query<&ComputedViewport>,
+) {
+    if !overlays.pixel_grid && !overlays.crosshair {
+        return;
+    }
+
+    let (viewport, sprite) = match (
+        computed_viewport.iter().next(),
+        selected_image.iter().next(),
+    ) {
+        (Some(viewport), Some(sprite)) => (viewport, sprite),
+        _ => return,
+    };
+
+    let image_size = match images.get(&sprite.image) {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let (projection, camera_transform) = main_camera.single();
+    let cursor = camera_transform.translation.truncate();
+    let half_visible = Vec2::new(viewport.width, viewport.height) * projection.scale / 2.0;
+    let visible_min = cursor - half_visible;
+    let visible_max = cursor + half_visible;
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.thickness = 1.0;
+
+    let zoom = 1.0 / projection.scale;
+    if overlays.pixel_grid && zoom >= app_data.config.settings.guides.pixel_grid_min_zoom {
+        // Only the grid lines inside both the image and the visible region.
+        let image_min = -image_size / 2.0;
+        let image_max = image_size / 2.0;
+        let min = visible_min.max(image_min);
+        let max = visible_max.min(image_max);
+
+        painter.color = ui.colors.outer_border.with_alpha(0.35);
+
+        let first_column = (min.x - image_min.x).ceil() as i32;
+        let last_column = (max.x - image_min.x).floor() as i32;
+        for column in first_column..=last_column {
+            let x = image_min.x + column as f32;
+            painter.line(Vec3::new(x, min.y, GUIDE_Z), Vec3::new(x, max.y, GUIDE_Z));
+        }
+
+        let first_row = (min.y - image_min.y).ceil() as i32;
+        let last_row = (max.y - image_min.y).floor() as i32;
+        for row in first_row..=last_row {
+            let y = image_min.y + row as f32;
+            painter.line(Vec3::new(min.x, y, GUIDE_Z), Vec3::new(max.x, y, GUIDE_Z));
+        }
+    }
+
+    if overlays.crosshair {
+        painter.color = ui.colors.inner_border;
+        painter.line(
+            Vec3::new(visible_min.x, cursor.y, GUIDE_Z),
+            Vec3::new(visible_max.x, cursor.y, GUIDE_Z),
+        );
+        painter.line(
+            Vec3::new(cursor.x, visible_min.y, GUIDE_Z),
+            Vec3::new(cursor.x, visible_max.y, GUIDE_Z),
+        );
+    }
+}
+
+pub fn update_cursor_readout(
+    images: Res<Assets<Image>>,
+    predictions: Res<Predictions>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    selected_bounding_box: Query<&BoundingBox, With<SelectedBoundingBox>>,
+    main_camera: Query<&Transform, With<MainCamera>>,
+    mut readout: Query<&mut Text, With<CursorReadout>>,
+) {
+    let image_size = match selected_image
+        .iter()
+        .next()
+        .and_then(|sprite| images.get(&sprite.image))
+    {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let camera_transform = main_camera.single();
+    let cursor = world_to_image_pixel(camera_transform.translation.truncate(), image_size).floor();
+
+    let mut text = format!("cursor: {}, {}", cursor.x, cursor.y);
+
+    if !predictions.entries.is_empty() {
+        text.push_str(&format!(" | conf >= {:.2}", predictions.threshold));
+    }
+
+    if let Some(bounding_box) = selected_bounding_box.iter().next() {
+        text.push_str(&format!(
+            " | box x: {} y: {} w: {} h: {}",
+            (bounding_box.x - bounding_box.width / 2.0).round(),
+            (bounding_box.y - bounding_box.height / 2.0).round(),
+            bounding_box.width.round(),
+            bounding_box.height.round(),
+        ));
+    }
+
+    for mut readout_text in readout.iter_mut() {
+        if readout_text.0 != text {
+            readout_text.0 = text.clone();
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::{key_bindings::KeyInput, resources::AppData, HelpUpdateNeeded, Ui, UiHelpOverlay};
+
+const HELP_FONT_SCALE: f32 = 0.7;
+const HELP_ACTION_WIDTH: f32 = 190.0;
+const HELP_SECTION_WIDTH: f32 = 330.0;
+
+pub fn toggle_help_overlay(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut help_overlay: Query<&mut Visibility, With<UiHelpOverlay>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.toggle_help) {
+        return;
+    }
+
+    for mut visibility in help_overlay.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => {
+                commands.spawn(HelpUpdateNeeded);
+                Visibility::Inherited
+            }
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Lists the live key map's bindings by section while the overlay is shown,
+/// followed by the modal bindings when modes are enabled.
+pub fn update_help_overlay(
+    mut commands: Commands,
+    app_data: Res<AppData>,
+    ui: Res<Ui>,
+    update_query: Query<Entity, With<HelpUpdateNeeded>>,
+    help_overlay: Query<(Entity, &Visibility), With<UiHelpOverlay>>,
+) {
+    if update_query.is_empty() {
+        return;
+    }
+
+    for update_eid in update_query.iter() {
+        commands.entity(update_eid).despawn();
+    }
+
+    let (help_overlay_eid, visibility) = match help_overlay.get_single() {
+        Ok(help_overlay) => help_overlay,
+        Err(_) => return,
+    };
+
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    let settings = &app_data.config.settings;
+    let mut sections = settings
+        .key_map
+        .sections()
+        .into_iter()
+        .map(|(title, actions)| {
+            let rows = actions
+                .into_iter()
+                .map(|(name, bindings)| (name.to_string(), bindings.to_string()))
+                .collect::<Vec<_>>();
+            (title.to_string(), rows)
+        })
+        .collect::<Vec<_>>();
+
+    if settings.modal.enabled {
+        let mut rows = settings
+            .modal
+            .key_map
+            .actions()
+            .into_iter()
+            .map(|(name, bindings)| (name.to_string(), bindings.to_string()))
+            .collect::<Vec<_>>();
+        rows.push((
+            "commands".to_string(),
+            "w, q, wq, goto <n>, class <name>, <action>".to_string(),
+        ));
+        sections.push(("Modes (normal mode keys win)".to_string(), rows));
+    }
+
+    commands
+        .entity(help_overlay_eid)
+        .despawn_descendants()
+        .with_children(|overlay| {
+            for (title, rows) in sections {
+                overlay
+                    .spawn(Node {
+                        width: Val::Px(HELP_SECTION_WIDTH),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    })
+                    .with_children(|section| {
+                        section.spawn(help_text(&ui, title, ui.colors.inner_border));
+
+                        for (name, bindings) in rows {
+                            section
+                                .spawn(Node {
+                                    flex_direction: FlexDirection::Row,
+                                    ..default()
+                                })
+                                .with_children(|row| {
+                                    row.spawn((
+                                        Node {
+                                            width: Val::Px(HELP_ACTION_WIDTH),
+                                            overflow: Overflow::clip(),
+                                            ..default()
+                                        },
+                                        help_text(&ui, name, ui.colors.text),
+                                    ));
+                                    row.spawn(help_text(&ui, bindings, ui.colors.text));
+                                });
+                        }
+                    });
+            }
+        });
+}
+
+fn help_text(ui: &Ui, text: String, color: Color) -> (Text, TextFont, TextColor) {
+    (
+        Text::new(text),
+        TextFont {
+            font: ui.font_handle.clone().unwrap(),
+            font_size: ui.font_size * HELP_FONT_SCALE,
+            ..Default::default()
+        },
+        TextColor::from(color),
+    )
+}
@@ -1,12 +1,17 @@
 use bevy::asset::LoadState;
 use bevy::prelude::*;
 use bevy::render::view::RenderLayers;
+use hashbrown::HashSet;
+use std::path::PathBuf;
 use yolo_io::ImageLabelPair;
 
+use crate::filters::NavigationFilter;
+use crate::image_cache::ImageCache;
+use crate::resources::{AppData, NavigationRepeat};
 use crate::utils::{default_hide, default_main_layer};
 use crate::SelectedImage;
 use crate::{ComputedViewport, FocusInViewport, ImageReady};
-use crate::{DebounceTimer, FileNameLabelUpdateNeeded, ImageLoading, UiLabelingIndexUpdateNeeded};
+use crate::{FileNameLabelUpdateNeeded, ImageLoading, UiLabelingIndexUpdateNeeded};
 
 #[derive(Debug, Clone, Component)]
 #[require(
@@ -21,10 +26,9 @@ pub struct TargetImage;
 
 pub fn start_image_load(
     commands: &mut Commands,
-    asset_server: Res<AssetServer>,
+    asset_server: &AssetServer,
     index: isize,
-    total_images: isize,
-    delay_between_images: f32,
+    index_label: String,
     valid_pairs: Vec<ImageLabelPair>,
 ) {
     debug!("Loading image at index: {}", index);
@@ -53,13 +57,9 @@ pub fn start_image_load(
             image: next_image_handle.clone(),
             ..Default::default()
         })
-        .insert(ImageLoading(next_image_handle.clone()))
-        .insert(DebounceTimer {
-            timer: Timer::from_seconds(delay_between_images, TimerMode::Once),
-        });
+        .insert(ImageLoading(next_image_handle.clone()));
 
     // Update index label
-    let index_label = format!("{}/{}", index + 1, total_images + 1);
     commands.spawn(UiLabelingIndexUpdateNeeded(index_label));
 
     // Update current file name label
@@ -131,3 +131,69 @@ pub fn image_state_system(
         }
     }
 }
+
+/// Keeps the images around the current one decoded in the `ImageCache`, so
+/// selecting them finds the asset already loaded.
+#[allow(clippy::too_many_arguments)]
+pub fn prefetch_neighbouring_images(
+    asset_server: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+    app_data: Res<AppData>,
+    navigation_filter: Res<NavigationFilter>,
+    repeat: Res<NavigationRepeat>,
+    mut image_cache: ResMut<ImageCache>,
+    mut last_index: Local<Option<isize>>,
+    mut keep: Local<HashSet<PathBuf>>,
+) {
+    // Record decoded sizes as prefetched images finish loading.
+    for entry in image_cache.entries.values_mut() {
+        if entry.bytes == 0 {
+            if let Some(image) = images.get(&entry.handle) {
+                entry.bytes = image.data.len();
+            }
+        }
+    }
+
+    // Scrubbing moves faster than images decode; wait for the key release.
+    if *last_index != Some(app_data.index) && !repeat.scrubbing {
+        *last_index = Some(app_data.index);
+
+        let valid_pairs = app_data.yolo_project.get_valid_pairs();
+        let current_index = app_data.index as usize;
+        let neighbours = app_data.config.settings.prefetch.neighbours as isize;
+
+        let mut wanted = vec![current_index];
+        for distance in 1..=neighbours {
+            for step in [distance, -distance] {
+                if let Some(index) = navigation_filter.step(current_index, step) {
+                    wanted.push(index);
+                }
+            }
+        }
+
+        keep.clear();
+
+        // Farthest first, so the current image ends up most recently used.
+        for index in wanted.into_iter().rev() {
+            let pair = &valid_pairs[index];
+            let image_path = match &pair.image_path {
+                Some(image_path) => image_path.clone(),
+                None => continue,
+            };
+
+            if image_cache.contains(&image_path) {
+                image_cache.touch(&image_path);
+            } else {
+                debug!("Prefetching image: {:?}", image_path);
+                let handle = asset_server.load::<Image>(image_path.to_string_lossy().into_owned());
+                image_cache.insert(image_path.clone(), handle, pair.label_file.clone());
+            }
+
+            keep.insert(image_path);
+        }
+    }
+
+    for image_path in image_cache.evict(&keep) {
+        debug!("Evicted image from cache: {:?}", image_path);
+    }
+}
@@ -1,44 +1,234 @@
 use crate::{
     bounding_boxes::{BoundingBox, SelectedBoundingBox},
-    resources::AppData,
-    CenterInViewport, ComputedViewport, DebounceTimer, FocusInViewport, MainCamera, SelectedImage,
+    filters::NavigationFilter,
+    image_cache::ImageCache,
+    key_bindings::{KeyChords, KeyInput},
+    resources::{AppData, BoxDrawing, NavigationRepeat},
+    thumbnails::ThumbnailCache,
+    CenterInViewport, ComputedViewport, FileNameLabelUpdateNeeded, FocusInViewport, MainCamera,
+    ScrubPreviewUpdateNeeded, SelectedImage, UiLabelingIndexUpdateNeeded,
 };
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 use itertools::Itertools;
 
 use super::{start_image_load, CornerHandle};
 
+/// Follows chord presses once input is collected, before `Update` systems
+/// read the key bindings.
+pub fn update_key_chords(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    app_data: Res<AppData>,
+    mut key_chords: ResMut<KeyChords>,
+) {
+    key_chords.update(
+        &keys,
+        &app_data.config.settings.key_map.actions(),
+        time.elapsed_secs(),
+    );
+}
+
+pub fn quit_app(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    if keyboard_input.just_pressed(&app_data.config.settings.key_map.quit) {
+        exit_events.send(AppExit::Success);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn image_selection_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keyboard_input: KeyInput,
+    time: Res<Time>,
     mut app_data: ResMut<AppData>,
+    mut repeat: ResMut<NavigationRepeat>,
+    navigation_filter: Res<NavigationFilter>,
+    image_cache: Res<ImageCache>,
+    thumbnail_cache: Res<ThumbnailCache>,
+    images: Res<Assets<Image>>,
     query_selected_images: Query<Entity, With<SelectedImage>>,
-    debounced_timer: Query<Entity, (With<DebounceTimer>, With<SelectedImage>)>,
     mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
 ) {
-    // Check if debounce timer is still running.
-    if debounced_timer.iter().count() > 0 {
+    let direction = if keyboard_input.pressed(&app_data.config.settings.key_map.next_image) {
+        1
+    } else if keyboard_input.pressed(&app_data.config.settings.key_map.previous_image) {
+        -1
+    } else {
+        0
+    };
+
+    let key_repeat = &app_data.config.settings.key_repeat;
+    let class_map = app_data.yolo_project.config.export.class_map.clone();
+
+    // Releasing the key after scrubbing loads the image it stopped on.
+    if direction == 0 {
+        if repeat.scrubbing {
+            commands.spawn(ScrubPreviewUpdateNeeded {
+                file_name: None,
+                thumbnail: None,
+            });
+
+            let index_label = navigation_filter.index_label(app_data.index as usize, &class_map);
+            replace_selected_image(
+                &mut commands,
+                &asset_server,
+                &app_data,
+                index_label,
+                &mut main_camera,
+                &query_selected_images,
+            );
+        }
+
+        *repeat = NavigationRepeat::default();
         return;
     }
 
-    if keyboard_input.pressed(KeyCode::ArrowRight) {
-        app_data.index += 1;
-    } else if keyboard_input.pressed(KeyCode::ArrowLeft) {
-        app_data.index -= 1;
+    // A fresh press steps once, then waits out the initial delay before
+    // repeating at the configured rate.
+    let steps = if direction != repeat.direction {
+        repeat.direction = direction;
+        repeat.countdown = key_repeat.initial_delay;
+        1
     } else {
+        let interval = 1.0 / key_repeat.repeat_rate.max(1.0);
+        repeat.countdown -= time.delta_secs();
+
+        let mut steps = 0;
+        while repeat.countdown <= 0.0 {
+            steps += 1;
+            repeat.countdown += interval;
+        }
+
+        if steps > 0 {
+            repeat.scrubbing = true;
+        }
+
+        steps
+    };
+
+    if steps == 0 {
         return;
     }
 
-    let valid_pairs = app_data.yolo_project.get_valid_pairs();
+    // Only walk the images matching the navigation filter.
+    let next_index = match navigation_filter.step(app_data.index as usize, direction * steps) {
+        Some(index) => index,
+        None => {
+            info!("No images match the navigation filter");
+            return;
+        }
+    };
+
+    app_data.index = next_index as isize;
+    let index_label = navigation_filter.index_label(next_index, &class_map);
+
+    if !repeat.scrubbing {
+        replace_selected_image(
+            &mut commands,
+            &asset_server,
+            &app_data,
+            index_label,
+            &mut main_camera,
+            &query_selected_images,
+        );
+        return;
+    }
 
-    if app_data.index < 0 {
-        app_data.index = valid_pairs.len() as isize - 1;
+    // While scrubbing only names and already decoded images or their
+    // filmstrip thumbnails are shown.
+    let image_path = match app_data
+        .yolo_project
+        .pair_at_index(app_data.index)
+        .and_then(|pair| pair.image_path)
+    {
+        Some(image_path) => image_path,
+        None => {
+            error!("Image path not found");
+            return;
+        }
+    };
+
+    let file_name = image_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    let thumbnail = image_cache
+        .entries
+        .get(&image_path)
+        .map(|entry| entry.handle.clone())
+        .filter(|handle| images.contains(handle))
+        .or_else(|| thumbnail_cache.thumbnails.get(&image_path).cloned());
+
+    commands.spawn(UiLabelingIndexUpdateNeeded(index_label));
+    commands.spawn(FileNameLabelUpdateNeeded(file_name.clone()));
+    commands.spawn(ScrubPreviewUpdateNeeded {
+        file_name: Some(file_name),
+        thumbnail,
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn cycle_navigation_filter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    keyboard_input: KeyInput,
+    mut app_data: ResMut<AppData>,
+    mut navigation_filter: ResMut<NavigationFilter>,
+    query_selected_images: Query<Entity, With<SelectedImage>>,
+    mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.cycle_filter) {
+        return;
     }
 
-    if app_data.index >= valid_pairs.len() as isize {
-        app_data.index = 0;
+    let valid_pairs = app_data.yolo_project.get_valid_pairs();
+    navigation_filter.cycle_next(&valid_pairs);
+
+    let class_map = app_data.yolo_project.config.export.class_map.clone();
+    info!(
+        "Navigation filter: {} ({} images)",
+        navigation_filter.filter.describe(&class_map),
+        navigation_filter.indices.len()
+    );
+
+    // Move to the nearest matching image if the current one is filtered out.
+    let current_index = app_data.index as usize;
+    match navigation_filter.nearest(current_index) {
+        Some(next_index) if next_index != current_index => {
+            app_data.index = next_index as isize;
+            let index_label = navigation_filter.index_label(next_index, &class_map);
+
+            replace_selected_image(
+                &mut commands,
+                &asset_server,
+                &app_data,
+                index_label,
+                &mut main_camera,
+                &query_selected_images,
+            );
+        }
+        _ => {
+            let index_label = navigation_filter.index_label(current_index, &class_map);
+            commands.spawn(UiLabelingIndexUpdateNeeded(index_label));
+        }
     }
+}
+
+pub fn replace_selected_image(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    app_data: &AppData,
+    index_label: String,
+    main_camera: &mut Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+    query_selected_images: &Query<Entity, With<SelectedImage>>,
+) {
+    let valid_pairs = app_data.yolo_project.get_valid_pairs();
 
     let (mut projection, mut camera_transform) = main_camera.single_mut();
 
@@ -47,13 +237,11 @@ pub fn image_selection_system(
     projection.scale = 1.0;
     camera_transform.translation = Vec3::new(0., 0., 0.);
 
-    let total_images = valid_pairs.len() as isize - 1;
     start_image_load(
-        &mut commands,
+        commands,
         asset_server,
         app_data.index,
-        total_images,
-        app_data.config.settings.delay_between_images,
+        index_label,
         valid_pairs,
     );
 
@@ -61,55 +249,200 @@ pub fn image_selection_system(
     for entity in query_selected_images.iter() {
         commands.entity(entity).despawn_recursive();
     }
+
+    // A corner placed on the old image doesn't carry over.
+    commands.insert_resource(BoxDrawing::default());
 }
 
 pub fn translate_image_system(
     mut main_camera: Query<&mut Transform, With<MainCamera>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keyboard_input: KeyInput,
     time: Res<Time>,
     app_data: Res<AppData>,
 ) {
     for mut main_camera in main_camera.iter_mut() {
         let mut translation = main_camera.translation;
-        if keyboard_input.pressed(app_data.config.settings.key_map.pan_up) {
+        if keyboard_input.pressed(&app_data.config.settings.key_map.pan_up) {
             translation.y -= app_data.config.settings.pan_factor.y * time.delta_secs();
         }
-        if keyboard_input.pressed(app_data.config.settings.key_map.pan_down) {
+        if keyboard_input.pressed(&app_data.config.settings.key_map.pan_down) {
             translation.y += app_data.config.settings.pan_factor.y * time.delta_secs();
         }
-        if keyboard_input.pressed(app_data.config.settings.key_map.pan_left) {
+        if keyboard_input.pressed(&app_data.config.settings.key_map.pan_left) {
             translation.x += app_data.config.settings.pan_factor.x * time.delta_secs();
         }
-        if keyboard_input.pressed(app_data.config.settings.key_map.pan_right) {
+        if keyboard_input.pressed(&app_data.config.settings.key_map.pan_right) {
             translation.x -= app_data.config.settings.pan_factor.x * time.delta_secs();
         }
         main_camera.translation = translation;
     }
 }
 
+/// Zooms in steps of `zoom_factor`. While the mouse is over the canvas the
+/// point under it stays in place, otherwise zoom centres on the camera.
 pub fn zoom_image_system(
-    mut query: Query<&mut OrthographicProjection, With<MainCamera>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+    keyboard_input: KeyInput,
     app_data: Res<AppData>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    computed_viewport: Query<&ComputedViewport>,
 ) {
-    for mut projection in query.iter_mut() {
+    for (mut projection, mut camera_transform) in query.iter_mut() {
         let mut scale = projection.scale;
         let zoom_factor = app_data.config.settings.zoom_factor;
-        if keyboard_input.pressed(app_data.config.settings.key_map.zoom_in) {
+        if keyboard_input.pressed(&app_data.config.settings.key_map.zoom_in) {
             scale *= zoom_factor;
         }
-        if keyboard_input.pressed(app_data.config.settings.key_map.zoom_out) {
+        if keyboard_input.pressed(&app_data.config.settings.key_map.zoom_out) {
             scale /= zoom_factor;
         }
+
+        if scale == projection.scale {
+            continue;
+        }
+
+        let cursor = match (window.get_single(), computed_viewport.iter().next()) {
+            (Ok(window), Some(viewport)) => {
+                cursor_world_position(window, viewport, &camera_transform, projection.scale)
+            }
+            _ => None,
+        };
+
+        if let Some(cursor) = cursor {
+            let camera = camera_transform.translation.truncate();
+            let camera = cursor - (cursor - camera) * scale / projection.scale;
+            camera_transform.translation.x = camera.x;
+            camera_transform.translation.y = camera.y;
+        }
+
         projection.scale = scale;
     }
 }
 
+/// World position under the mouse cursor, when it is over the canvas.
+fn cursor_world_position(
+    window: &Window,
+    viewport: &ComputedViewport,
+    camera_transform: &Transform,
+    scale: f32,
+) -> Option<Vec2> {
+    // The canvas node and the render target are both sized in physical pixels.
+    let size = Vec2::new(viewport.width, viewport.height);
+    let top_left = viewport.translation.truncate() - size / 2.0;
+    let on_canvas = window.physical_cursor_position()? - top_left;
+    if on_canvas.x < 0.0 || on_canvas.y < 0.0 || on_canvas.x > size.x || on_canvas.y > size.y {
+        return None;
+    }
+
+    // Window y grows downwards, world y upwards.
+    let from_center = on_canvas - size / 2.0;
+    Some(camera_transform.translation.truncate() + Vec2::new(from_center.x, -from_center.y) * scale)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn zoom_preset_system(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<(Entity, &Sprite), With<SelectedImage>>,
+    selected_bounding_box: Query<(Entity, &BoundingBox), With<SelectedBoundingBox>>,
+    computed_viewport: Query<&ComputedViewport>,
+    mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+
+    if keyboard_input.just_pressed(&key_map.zoom_fit_image) {
+        if let Some((image_eid, sprite)) = selected_image.iter().next() {
+            if let Some(image) = images.get(&sprite.image) {
+                commands
+                    .entity(image_eid)
+                    .insert(FocusInViewport {
+                        width: image.width() as f32,
+                        height: image.height() as f32,
+                    })
+                    .insert(CenterInViewport);
+            }
+        }
+        return;
+    }
+
+    if keyboard_input.just_pressed(&key_map.zoom_fit_selection) {
+        match selected_bounding_box.iter().next() {
+            Some((bounding_box_eid, bounding_box)) => {
+                commands
+                    .entity(bounding_box_eid)
+                    .insert(FocusInViewport {
+                        width: bounding_box.width,
+                        height: bounding_box.height,
+                    })
+                    .insert(CenterInViewport);
+            }
+            None => info!("No bounding box selected to fit"),
+        }
+        return;
+    }
+
+    // A scale of 1.0 draws one image pixel per pixel of the render target.
+    let pixels_per_image_pixel = [
+        (&key_map.zoom_actual_pixels, 1.0),
+        (&key_map.zoom_2x, 2.0),
+        (&key_map.zoom_4x, 4.0),
+        (&key_map.zoom_8x, 8.0),
+    ]
+    .into_iter()
+    .find(|(key, _)| keyboard_input.just_pressed(key))
+    .map(|(_, zoom)| zoom);
+
+    let zoom = match pixels_per_image_pixel {
+        Some(zoom) => zoom,
+        None => return,
+    };
+
+    let (mut projection, mut camera_transform) = main_camera.single_mut();
+    projection.scale = 1.0 / zoom;
+
+    let image_size = selected_image
+        .iter()
+        .next()
+        .and_then(|(_, sprite)| images.get(&sprite.image))
+        .map(|image| Vec2::new(image.width() as f32, image.height() as f32));
+
+    if let (Some(image_size), Some(viewport)) = (image_size, computed_viewport.iter().next()) {
+        camera_transform.translation = snap_to_pixel_grid(
+            camera_transform.translation,
+            image_size,
+            Vec2::new(viewport.width, viewport.height),
+            zoom,
+        );
+    }
+}
+
+/// Moves the camera so image pixel edges land on render target pixels at an
+/// integer zoom, which keeps nearest sampling from doubling rows or columns.
+fn snap_to_pixel_grid(translation: Vec3, image_size: Vec2, viewport_size: Vec2, zoom: f32) -> Vec3 {
+    let half_visible = viewport_size / (2.0 * zoom);
+    let image_left = -image_size.x / 2.0;
+    let image_top = image_size.y / 2.0;
+
+    let left = translation.x - half_visible.x;
+    let top = translation.y + half_visible.y;
+
+    let snapped_left = image_left + ((left - image_left) * zoom).round() / zoom;
+    let snapped_top = image_top - ((image_top - top) * zoom).round() / zoom;
+
+    Vec3::new(
+        snapped_left + half_visible.x,
+        snapped_top - half_visible.y,
+        translation.z,
+    )
+}
+
 pub fn cycle_bounding_box_selection(
     mut commands: Commands,
     app_data: ResMut<AppData>,
     selected_image: Query<(Entity, &Sprite, &SelectedImage)>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keyboard_input: KeyInput,
     bounding_boxes: Query<(Entity, &BoundingBox)>,
     selected_bounding_box: Query<(Entity, &BoundingBox), With<SelectedBoundingBox>>,
     images: Res<Assets<Image>>,
@@ -125,7 +458,7 @@ pub fn cycle_bounding_box_selection(
     //    and fit the viewport to the image.
 
     // Change on tab press
-    if keyboard_input.just_pressed(app_data.config.settings.key_map.cycle_selection) {
+    if keyboard_input.just_pressed(&app_data.config.settings.key_map.cycle_selection) {
         for entity in corner_handles.iter() {
             commands.entity(entity).despawn_recursive();
         }
@@ -188,7 +521,7 @@ pub fn cycle_bounding_box_selection(
 
 pub fn select_bounding_box_nearest_center(
     mut commands: Commands,
-    input: Res<ButtonInput<KeyCode>>,
+    input: KeyInput,
     viewport: Query<&ComputedViewport>,
     bounding_boxes: Query<(Entity, &BoundingBox, &Transform)>,
     selected_bounding_box: Query<Entity, With<SelectedBoundingBox>>,
@@ -207,7 +540,7 @@ pub fn select_bounding_box_nearest_center(
 
     let main_camera_transform = main_camera.single();
 
-    if input.just_released(app_data.config.settings.key_map.change_selection) {
+    if input.just_released(&app_data.config.settings.key_map.change_selection) {
         for entity in selected_bounding_box.iter() {
             commands.entity(entity).remove::<SelectedBoundingBox>();
         }
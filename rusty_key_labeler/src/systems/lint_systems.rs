@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+use crate::{
+    filters::NavigationFilter,
+    key_bindings::KeyInput,
+    lint::{lint_project, LintWalk},
+    resources::{AppData, PendingBoxFocus},
+    stats::{read_image_size, ImageSizeCache},
+    MainCamera, SelectedImage, UiLabelingIndexUpdateNeeded,
+};
+
+use super::replace_selected_image;
+
+/// Lints the whole project and opens the image of the next issue after the
+/// last one visited. The offending box is focused by `focus_pending_box`.
+#[allow(clippy::too_many_arguments)]
+pub fn next_lint_issue(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    keyboard_input: KeyInput,
+    mut app_data: ResMut<AppData>,
+    navigation_filter: Res<NavigationFilter>,
+    mut lint_walk: ResMut<LintWalk>,
+    mut pending_box_focus: ResMut<PendingBoxFocus>,
+    mut image_sizes: Local<ImageSizeCache>,
+    query_selected_images: Query<Entity, With<SelectedImage>>,
+    mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.next_issue) {
+        return;
+    }
+
+    // Linting again on every press keeps the walk current with edits.
+    let issues = lint_project(
+        &app_data.yolo_project.data.pairs,
+        &app_data.yolo_project.config.export.class_map,
+        &app_data.config.settings.lint,
+        |path| {
+            *image_sizes
+                .entry(path.to_path_buf())
+                .or_insert_with(|| read_image_size(path))
+        },
+    );
+
+    let openable = issues
+        .iter()
+        .filter(|issue| issue.pair_index.is_some())
+        .collect::<Vec<_>>();
+    let rejected = issues.len() - openable.len();
+    if rejected > 0 {
+        warn!(
+            "{} label files were rejected when the project loaded; run `rusty_key_labeler lint` to list them",
+            rejected
+        );
+    }
+
+    let issue = match lint_walk.next(&issues) {
+        Some(issue) => issue,
+        None => {
+            info!("No lint issues in images that can be opened");
+            return;
+        }
+    };
+
+    let (pair_index, entry) = match (issue.pair_index, issue.entry) {
+        (Some(pair_index), Some(entry)) => (pair_index, entry),
+        _ => return,
+    };
+
+    info!(
+        "Issue {} of {}: {}",
+        openable
+            .iter()
+            .position(|other| *other == issue)
+            .map_or(0, |position| position + 1),
+        openable.len(),
+        issue.describe()
+    );
+
+    lint_walk.last = Some((pair_index, entry));
+    pending_box_focus.0 = Some((pair_index, entry));
+
+    let index_label = format!(
+        "{}  {}",
+        navigation_filter.index_label(pair_index, &app_data.yolo_project.config.export.class_map),
+        issue.kind.describe()
+    );
+
+    if app_data.index as usize == pair_index {
+        commands.spawn(UiLabelingIndexUpdateNeeded(index_label));
+        return;
+    }
+
+    app_data.index = pair_index as isize;
+    replace_selected_image(
+        &mut commands,
+        &asset_server,
+        &app_data,
+        index_label,
+        &mut main_camera,
+        &query_selected_images,
+    );
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+
+use crate::{
+    bounding_boxes::{BoundingBox, ContainsBoundingBoxes},
+    key_bindings::KeyInput,
+    resources::AppData,
+    ComputedViewport, MainCamera, MinimapBox, MinimapViewportRect, SelectedImage, UiMinimap,
+};
+
+/// Points the minimap at the newly loaded image and outlines its boxes.
+pub fn rebuild_minimap(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    loaded_image: Query<&Sprite, (With<SelectedImage>, Added<ContainsBoundingBoxes>)>,
+    bounding_boxes: Query<&BoundingBox>,
+    mut minimap: Query<(Entity, &mut ImageNode, &mut Node), With<UiMinimap>>,
+    minimap_boxes: Query<Entity, With<MinimapBox>>,
+) {
+    let sprite = match loaded_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    let image_size = match images.get(&sprite.image) {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let (minimap_eid, mut image_node, mut node) = match minimap.iter_mut().next() {
+        Some(minimap) => minimap,
+        None => return,
+    };
+
+    image_node.image = sprite.image.clone();
+    node.aspect_ratio = Some(image_size.x / image_size.y);
+
+    for entity in minimap_boxes.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for bounding_box in bounding_boxes.iter() {
+        let minimap_box_eid = commands
+            .spawn((
+                Name::new(format!("minimap_box_{}", bounding_box.index)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(
+                        (bounding_box.x - bounding_box.width / 2.0) / image_size.x * 100.0,
+                    ),
+                    top: Val::Percent(
+                        (bounding_box.y - bounding_box.height / 2.0) / image_size.y * 100.0,
+                    ),
+                    width: Val::Percent(bounding_box.width / image_size.x * 100.0),
+                    height: Val::Percent(bounding_box.height / image_size.y * 100.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                BorderColor(bounding_box.class_color),
+                MinimapBox,
+            ))
+            .id();
+
+        commands.entity(minimap_eid).add_child(minimap_box_eid);
+    }
+}
+
+/// Keeps the minimap's rectangle on the part of the image the camera sees.
+pub fn update_minimap_viewport(
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    main_camera: Query<(&OrthographicProjection, &Transform), With<MainCamera>>,
+    computed_viewport: Query<&ComputedViewport>,
+    mut viewport_rect: Query<&mut Node, With<MinimapViewportRect>>,
+) {
+    let (viewport, sprite) = match (
+        computed_viewport.iter().next(),
+        selected_image.iter().next(),
+    ) {
+        (Some(viewport), Some(sprite)) => (viewport, sprite),
+        _ => return,
+    };
+
+    let image_size = match images.get(&sprite.image) {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let (projection, camera_transform) = main_camera.single();
+
+    // The image is centered on the world origin with y pointing up, and the
+    // camera sees the viewport's size scaled by its projection.
+    let visible_size = Vec2::new(viewport.width, viewport.height) * projection.scale;
+    let top_left = Vec2::new(
+        camera_transform.translation.x - visible_size.x / 2.0 + image_size.x / 2.0,
+        image_size.y / 2.0 - (camera_transform.translation.y + visible_size.y / 2.0),
+    );
+
+    let left = Val::Percent(top_left.x / image_size.x * 100.0);
+    let top = Val::Percent(top_left.y / image_size.y * 100.0);
+    let width = Val::Percent(visible_size.x / image_size.x * 100.0);
+    let height = Val::Percent(visible_size.y / image_size.y * 100.0);
+
+    for mut node in viewport_rect.iter_mut() {
+        // Avoid relayouts while the camera is still.
+        if node.left != left || node.top != top || node.width != width || node.height != height {
+            node.left = left;
+            node.top = top;
+            node.width = width;
+            node.height = height;
+        }
+    }
+}
+
+pub fn toggle_minimap(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut minimap: Query<&mut Visibility, With<UiMinimap>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.toggle_minimap) {
+        return;
+    }
+
+    for mut visibility in minimap.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
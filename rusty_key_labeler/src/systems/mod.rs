@@ -1,14 +1,48 @@
 pub mod bounding_box_systems;
+pub mod comparison_systems;
+pub mod config_systems;
+pub mod detector_systems;
+pub mod edit_systems;
+pub mod filmstrip;
+pub mod guides;
+pub mod help_systems;
 pub mod image_systems;
 pub mod input;
+pub mod lint_systems;
+pub mod minimap;
+pub mod modal_systems;
+pub mod prediction_systems;
+pub mod review_systems;
+pub mod session_systems;
 pub mod setup;
+pub mod shrink_systems;
+pub mod sprite_grid_systems;
+pub mod stats_systems;
+pub mod suggestion_systems;
+pub mod text_entry;
 pub mod ui;
-pub mod util_systems;
 pub mod viewport;
 pub use bounding_box_systems::*;
+pub use comparison_systems::*;
+pub use config_systems::*;
+pub use detector_systems::*;
+pub use edit_systems::*;
+pub use filmstrip::*;
+pub use guides::*;
+pub use help_systems::*;
 pub use image_systems::*;
 pub use input::*;
+pub use lint_systems::*;
+pub use minimap::*;
+pub use modal_systems::*;
+pub use prediction_systems::*;
+pub use review_systems::*;
+pub use session_systems::*;
 pub use setup::*;
+pub use shrink_systems::*;
+pub use sprite_grid_systems::*;
+pub use stats_systems::*;
+pub use suggestion_systems::*;
+pub use text_entry::*;
 pub use ui::*;
-pub use util_systems::*;
 pub use viewport::*;
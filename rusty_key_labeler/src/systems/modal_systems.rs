@@ -0,0 +1,418 @@
+use bevy::prelude::*;
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bounding_boxes::{BoundingBox, SelectedBoundingBox},
+    filters::NavigationFilter,
+    image_cache::ImageCache,
+    key_bindings::KeyBindings,
+    modal::{count_digit, counted_entries, InputMode, ModalAction, ModalCommand, ModalState},
+    resources::{AppData, TextEntry, TextEntryTarget},
+    review::ReviewManifest,
+    settings::MAIN_LAYER,
+    utils::image_pixel_to_world,
+    LabelEditNeeded, MainCamera, SelectedImage, Ui, UiModeIndicator,
+};
+
+use super::{
+    current_label_file, replace_selected_image, save_review_manifest, selected_image_size,
+    write_session_state,
+};
+
+const VISUAL_Z: f32 = 440.0;
+
+/// Run condition keeping box drawing to insert mode when modes are enabled.
+pub fn box_drawing_enabled(app_data: Res<AppData>, modal_state: Res<ModalState>) -> bool {
+    !app_data.config.settings.modal.enabled || modal_state.mode == InputMode::Insert
+}
+
+/// Handles the modal layer's keys and command line before `Update`, hiding
+/// the keys it uses from the main key map. Runs after `capture_text_entry`.
+pub fn handle_modal_keys(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    app_data: Res<AppData>,
+    mut modal_state: ResMut<ModalState>,
+    mut text_entry: ResMut<TextEntry>,
+    mut injected: Local<Vec<KeyCode>>,
+) {
+    // Keys pressed for a `:` action last frame.
+    for key in injected.drain(..) {
+        keys.reset(key);
+    }
+
+    let settings = &app_data.config.settings.modal;
+    if !settings.enabled {
+        return;
+    }
+
+    if modal_state.visual_index != app_data.index {
+        modal_state.visual_index = app_data.index;
+        modal_state.visual_selection.clear();
+    }
+
+    if matches!(text_entry.submitted, Some((TextEntryTarget::Command, _))) {
+        let line = text_entry
+            .submitted
+            .take()
+            .map(|(_, line)| line)
+            .unwrap_or_default();
+        let actions = app_data.config.settings.key_map.actions();
+        let names = actions.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+
+        match ModalCommand::parse(&line, &names) {
+            Ok(ModalCommand::Action(name)) => {
+                let press = actions
+                    .iter()
+                    .find(|(action, _)| *action == name)
+                    .and_then(|(_, bindings)| bindings.0.iter().find(|binding| !binding.is_chord()))
+                    .map(|binding| binding.0[0]);
+
+                match press {
+                    Some(press) => {
+                        for (held, key) in [
+                            (press.modifiers.ctrl, KeyCode::ControlLeft),
+                            (press.modifiers.shift, KeyCode::ShiftLeft),
+                            (press.modifiers.alt, KeyCode::AltLeft),
+                            (true, press.key),
+                        ] {
+                            if held {
+                                keys.press(key);
+                                injected.push(key);
+                            }
+                        }
+                        // Leaves the injected press to the main key map.
+                        return;
+                    }
+                    None => warn!(":{} is only bound to chords", name),
+                }
+            }
+            Ok(command) => modal_state.pending.push(ModalAction::Run(command)),
+            Err(e) => warn!("{}", e),
+        }
+    }
+
+    if text_entry.target.is_some() {
+        return;
+    }
+
+    let key_map = &settings.key_map;
+    let mode = modal_state.mode;
+    let mut consumed = None;
+
+    if let Some(key) = pressed_key(&key_map.normal_mode, &keys) {
+        modal_state.set_mode(InputMode::Normal);
+        consumed = Some(key);
+    } else if mode == InputMode::Insert {
+        // Every other key keeps its main binding while drawing.
+    } else if let Some(key) = pressed_key(&key_map.command_line, &keys) {
+        text_entry.start(TextEntryTarget::Command);
+        consumed = Some(key);
+    } else if let Some(key) = pressed_key(&key_map.insert_mode, &keys) {
+        modal_state.set_mode(InputMode::Insert);
+        consumed = Some(key);
+    } else if let Some(key) = pressed_key(&key_map.visual_mode, &keys) {
+        modal_state.set_mode(match mode {
+            InputMode::Visual => InputMode::Normal,
+            _ => InputMode::Visual,
+        });
+        consumed = Some(key);
+    } else if let Some(key) = pressed_key(&key_map.next_image, &keys) {
+        let count = modal_state.take_count();
+        modal_state
+            .pending
+            .push(ModalAction::StepImages(count as isize));
+        consumed = Some(key);
+    } else if let Some(key) = pressed_key(&key_map.previous_image, &keys) {
+        let count = modal_state.take_count();
+        modal_state
+            .pending
+            .push(ModalAction::StepImages(-(count as isize)));
+        consumed = Some(key);
+    } else if let Some(key) = pressed_key(&key_map.delete_boxes, &keys) {
+        let count = modal_state.take_count();
+        modal_state.pending.push(ModalAction::DeleteBoxes(count));
+        consumed = Some(key);
+    } else if let Some((key, digit)) = keys.get_just_pressed().find_map(|key| {
+        count_digit(*key)
+            .filter(|digit| *digit > 0 || modal_state.count.is_some())
+            .map(|digit| (*key, digit))
+    }) {
+        modal_state.push_digit(digit);
+        consumed = Some(key);
+    } else if modal_state.count.is_some() && keys.get_just_pressed().next().is_some() {
+        // Any other key drops a count it doesn't use.
+        modal_state.count = None;
+    }
+
+    if let Some(key) = consumed {
+        keys.reset(key);
+    }
+}
+
+/// The key of a single press binding pressed this frame.
+fn pressed_key(bindings: &KeyBindings, keys: &ButtonInput<KeyCode>) -> Option<KeyCode> {
+    bindings
+        .0
+        .iter()
+        .find_map(|binding| match binding.0.as_slice() {
+            [press] if press.just_pressed(keys) => Some(press.key),
+            _ => None,
+        })
+}
+
+/// Runs what `handle_modal_keys` queued: counted steps and deletes, and
+/// command line commands.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_modal_actions(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut app_data: ResMut<AppData>,
+    mut modal_state: ResMut<ModalState>,
+    navigation_filter: Res<NavigationFilter>,
+    mut review_manifest: ResMut<ReviewManifest>,
+    image_cache: Res<ImageCache>,
+    mut exit_events: EventWriter<AppExit>,
+    query_selected_images: Query<Entity, With<SelectedImage>>,
+    selected_sprites: Query<&Sprite, With<SelectedImage>>,
+    selected_bounding_box: Query<&BoundingBox, With<SelectedBoundingBox>>,
+    mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+) {
+    if modal_state.pending.is_empty() {
+        return;
+    }
+
+    let selected_entry = selected_bounding_box
+        .iter()
+        .next()
+        .map(|bounding_box| bounding_box.index);
+
+    for action in std::mem::take(&mut modal_state.pending) {
+        let index = match action {
+            ModalAction::StepImages(steps) => {
+                match navigation_filter.step(app_data.index as usize, steps) {
+                    Some(index) => Some(index),
+                    None => {
+                        info!("No images match the navigation filter");
+                        None
+                    }
+                }
+            }
+            // Numbered like the labeling index, within the navigation filter.
+            ModalAction::Run(ModalCommand::GoTo(number)) => {
+                let images = navigation_filter.indices.len();
+                match number.checked_sub(1) {
+                    Some(position) if position < images => {
+                        Some(navigation_filter.indices[position])
+                    }
+                    _ => {
+                        warn!("Image {} is not between 1 and {}", number, images);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(index) = index {
+            app_data.index = index as isize;
+            let index_label = navigation_filter
+                .index_label(index, &app_data.yolo_project.config.export.class_map);
+            replace_selected_image(
+                &mut commands,
+                &asset_server,
+                &app_data,
+                index_label,
+                &mut main_camera,
+                &query_selected_images,
+            );
+            continue;
+        }
+
+        let targets = match modal_state.visual_selection.is_empty() {
+            false => modal_state.visual_selection.iter().copied().collect(),
+            true => selected_entry.into_iter().collect::<Vec<_>>(),
+        };
+
+        match action {
+            ModalAction::DeleteBoxes(count) => {
+                if targets.is_empty() {
+                    info!("No bounding box selected to delete");
+                    continue;
+                }
+
+                let sprite = match selected_sprites.iter().next() {
+                    Some(sprite) => sprite,
+                    None => continue,
+                };
+
+                let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+                    Some(label_file) => label_file,
+                    None => {
+                        error!("Pair not found");
+                        continue;
+                    }
+                };
+
+                // Without a visual selection, the selected box and the ones after it.
+                let entries = label_file.entries.len();
+                let targets = match (modal_state.visual_selection.is_empty(), selected_entry) {
+                    (true, Some(entry)) => counted_entries(entry, count, entries).collect(),
+                    _ => targets
+                        .into_iter()
+                        .filter(|entry| *entry < entries)
+                        .collect::<Vec<_>>(),
+                };
+                for entry in targets.iter().rev() {
+                    label_file.entries.remove(*entry);
+                }
+
+                if review_manifest.remove_boxes(&label_file.stem, &targets) {
+                    save_review_manifest(&review_manifest, &app_data);
+                }
+
+                commands.spawn(LabelEditNeeded {
+                    label_file,
+                    select: None,
+                });
+                modal_state.set_mode(InputMode::Normal);
+            }
+            ModalAction::Run(ModalCommand::Class(name)) => {
+                let class_map = &app_data.yolo_project.config.export.class_map;
+                let class = class_map
+                    .iter()
+                    .find(|(_, class_name)| class_name.eq_ignore_ascii_case(&name))
+                    .map(|(class, _)| *class)
+                    .or_else(|| {
+                        name.parse()
+                            .ok()
+                            .filter(|class| class_map.contains_key(class))
+                    });
+
+                let class = match class {
+                    Some(class) => class,
+                    None => {
+                        warn!("Unknown class '{}'", name);
+                        continue;
+                    }
+                };
+
+                info!("Active class: {}", class_map[&class]);
+                app_data.active_class = Some(class);
+
+                let sprite = match selected_sprites.iter().next() {
+                    Some(sprite) if !targets.is_empty() => sprite,
+                    _ => continue,
+                };
+
+                let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+                    Some(label_file) => label_file,
+                    None => {
+                        error!("Pair not found");
+                        continue;
+                    }
+                };
+
+                for entry in targets.iter() {
+                    if let Some(entry) = label_file.entries.get_mut(*entry) {
+                        entry.class = class;
+                    }
+                }
+
+                commands.spawn(LabelEditNeeded {
+                    label_file,
+                    select: selected_entry,
+                });
+                modal_state.set_mode(InputMode::Normal);
+            }
+            ModalAction::Run(ModalCommand::Write | ModalCommand::WriteQuit) => {
+                if let Ok((projection, camera_transform)) = main_camera.get_single() {
+                    write_session_state(
+                        &app_data,
+                        &navigation_filter,
+                        projection,
+                        camera_transform,
+                    );
+                }
+
+                if let Err(e) = review_manifest.save(&app_data.config.output_path) {
+                    error!("Unable to save review manifest: {}", e);
+                }
+
+                // Label edits are written as they are made.
+                info!("Saved session and review state");
+
+                if matches!(action, ModalAction::Run(ModalCommand::WriteQuit)) {
+                    exit_events.send(AppExit::Success);
+                }
+            }
+            ModalAction::Run(ModalCommand::Quit) => {
+                exit_events.send(AppExit::Success);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adds each box selected while in visual mode to the visual selection.
+pub fn extend_visual_selection(
+    mut modal_state: ResMut<ModalState>,
+    selected_bounding_box: Query<&BoundingBox, With<SelectedBoundingBox>>,
+) {
+    if modal_state.mode != InputMode::Visual {
+        return;
+    }
+
+    for bounding_box in selected_bounding_box.iter() {
+        if !modal_state.visual_selection.contains(&bounding_box.index) {
+            modal_state.visual_selection.insert(bounding_box.index);
+        }
+    }
+}
+
+pub fn draw_visual_selection(
+    mut painter: ShapePainter,
+    modal_state: Res<ModalState>,
+    ui: Res<Ui>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<&BoundingBox>,
+) {
+    if modal_state.visual_selection.is_empty() {
+        return;
+    }
+
+    let image_size = match selected_image_size(&images, &selected_image) {
+        Some((_, image_size)) => image_size,
+        None => return,
+    };
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.thickness = 3.0;
+    painter.hollow = true;
+    painter.color = ui.colors.inner_border;
+
+    for bounding_box in bounding_boxes.iter() {
+        if !modal_state.visual_selection.contains(&bounding_box.index) {
+            continue;
+        }
+
+        let rect = bounding_box.pixel_rect();
+        painter.set_translation(image_pixel_to_world(rect.center(), image_size).extend(VISUAL_Z));
+        painter.rect(rect.size());
+    }
+}
+
+pub fn update_mode_indicator(
+    app_data: Res<AppData>,
+    modal_state: Res<ModalState>,
+    mut indicator: Query<&mut Text, With<UiModeIndicator>>,
+) {
+    if !modal_state.is_changed() || !app_data.config.settings.modal.enabled {
+        return;
+    }
+
+    for mut text in indicator.iter_mut() {
+        text.0 = format!("-- {} --", modal_state.describe());
+    }
+}
@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bounding_boxes::{BoundingBox, BoundingBoxPainter},
+    image_cache::ImageCache,
+    key_bindings::KeyInput,
+    labels::entry_pixel_rect,
+    predictions::{read_predicted_entries, Predictions},
+    resources::AppData,
+    settings::MAIN_LAYER,
+    suggestions::overlaps_existing,
+    utils::image_pixel_to_world,
+    LabelEditNeeded, SelectedImage,
+};
+
+use super::current_label_file;
+
+const PREDICTION_Z: f32 = 400.0;
+
+/// Reads the prediction set's labels whenever the current image changes.
+pub fn load_predictions(app_data: Res<AppData>, mut predictions: ResMut<Predictions>) {
+    let directory = match &app_data.config.settings.predictions.labels {
+        Some(directory) => directory,
+        None => return,
+    };
+
+    let pair = match app_data.yolo_project.pair_at_index(app_data.index) {
+        Some(pair) => pair,
+        None => return,
+    };
+
+    if predictions.image_path == pair.image_path {
+        return;
+    }
+
+    predictions.image_path = pair.image_path.clone();
+    predictions.entries = match read_predicted_entries(directory, &pair.name) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Unable to read predictions: {}", e);
+            vec![]
+        }
+    };
+}
+
+pub fn adjust_confidence_threshold(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut predictions: ResMut<Predictions>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+    let step = app_data.config.settings.predictions.threshold_step;
+
+    let change = if keyboard_input.just_pressed(&key_map.raise_confidence_threshold) {
+        step
+    } else if keyboard_input.just_pressed(&key_map.lower_confidence_threshold) {
+        -step
+    } else {
+        return;
+    };
+
+    predictions.threshold = (predictions.threshold + change).clamp(0.0, 1.0);
+    info!("Confidence threshold: {:.2}", predictions.threshold);
+}
+
+/// Copies every visible prediction into the ground truth labels.
+#[allow(clippy::too_many_arguments)]
+pub fn accept_predictions(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    images: Res<Assets<Image>>,
+    predictions: Res<Predictions>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<&BoundingBox>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.accept_predictions) {
+        return;
+    }
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    let image_size = match images.get(&sprite.image) {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let class_map = &app_data.yolo_project.config.export.class_map;
+    let max_overlap = app_data.config.settings.suggestions.max_overlap;
+    let existing = bounding_boxes
+        .iter()
+        .map(BoundingBox::pixel_rect)
+        .collect::<Vec<Rect>>();
+
+    let accepted = predictions
+        .visible()
+        .filter(|predicted| class_map.contains_key(&predicted.entry.class))
+        .map(|predicted| predicted.entry.clone())
+        .filter(|entry| {
+            !overlaps_existing(entry_pixel_rect(entry, image_size), &existing, max_overlap)
+        })
+        .collect::<Vec<_>>();
+
+    if accepted.is_empty() {
+        info!(
+            "No new predictions at or above confidence {:.2}",
+            predictions.threshold
+        );
+        return;
+    }
+
+    let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+        Some(label_file) => label_file,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    info!("Accepted {} predictions", accepted.len());
+    label_file.entries.extend(accepted);
+
+    commands.spawn(LabelEditNeeded {
+        label_file,
+        select: None,
+    });
+}
+
+/// Outlines predictions at or above the threshold in their class colour,
+/// with opacity following confidence.
+pub fn draw_predictions(
+    mut painter: ShapePainter,
+    predictions: Res<Predictions>,
+    bb_painter: Res<BoundingBoxPainter>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+) {
+    if predictions.entries.is_empty() {
+        return;
+    }
+
+    let image_size = match selected_image
+        .iter()
+        .next()
+        .and_then(|sprite| images.get(&sprite.image))
+    {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.thickness = 2.0;
+    painter.hollow = true;
+
+    let class_colors = &bb_painter.bounding_box_settings.class_color_map;
+    for predicted in predictions.visible() {
+        let color = match class_colors.get(predicted.entry.class as usize) {
+            Some(color) => Color::from(*color),
+            None => continue,
+        };
+
+        let rect = entry_pixel_rect(&predicted.entry, image_size);
+        painter.color = color.with_alpha(predicted.confidence_or_certain().clamp(0.15, 1.0));
+        painter
+            .set_translation(image_pixel_to_world(rect.center(), image_size).extend(PREDICTION_Z));
+        painter.rect(rect.size());
+    }
+}
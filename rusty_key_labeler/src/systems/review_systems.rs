@@ -0,0 +1,323 @@
+use bevy::{
+    color::palettes::tailwind::{AMBER_500, GREEN_500, RED_500, SKY_500},
+    prelude::*,
+};
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bounding_boxes::{BoundingBox, ContainsBoundingBoxes, SelectedBoundingBox},
+    filters::NavigationFilter,
+    key_bindings::KeyInput,
+    resources::{AppData, PendingBoxFocus, TextEntry, TextEntryTarget},
+    review::{BoxFlag, ReviewManifest, ReviewStatus},
+    settings::MAIN_LAYER,
+    utils::image_pixel_to_world,
+    FilmstripUpdateNeeded, MainCamera, SelectAfterReload, SelectedImage, Ui,
+    UiLabelingIndexUpdateNeeded, UiReviewBadge,
+};
+
+use super::{replace_selected_image, selected_image_size};
+
+const FLAG_Z: f32 = 430.0;
+/// Marker radius in screen pixels.
+const FLAG_RADIUS: f32 = 5.0;
+
+/// Approves or flags the current image, pressing again returns it to
+/// unreviewed, or starts typing a reviewer comment.
+pub fn review_image(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut review_manifest: ResMut<ReviewManifest>,
+    mut navigation_filter: ResMut<NavigationFilter>,
+    mut text_entry: ResMut<TextEntry>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+
+    let status = if keyboard_input.just_pressed(&key_map.approve_image) {
+        ReviewStatus::Approved
+    } else if keyboard_input.just_pressed(&key_map.flag_needs_fix) {
+        ReviewStatus::NeedsFix
+    } else if keyboard_input.just_pressed(&key_map.comment_image) {
+        text_entry.start(TextEntryTarget::ImageComment);
+        return;
+    } else {
+        return;
+    };
+
+    let pair = match app_data.yolo_project.pair_at_index(app_data.index) {
+        Some(pair) => pair,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    let status = review_manifest.toggle_status(&pair.name, status);
+    info!("Marked {} {}", pair.name, status.describe());
+    save_review_manifest(&review_manifest, &app_data);
+
+    // The image stays open even when the filter no longer matches it.
+    navigation_filter.set_review_statuses(
+        review_manifest.statuses(),
+        &app_data.yolo_project.get_valid_pairs(),
+    );
+    commands.spawn(UiLabelingIndexUpdateNeeded(navigation_filter.index_label(
+        app_data.index as usize,
+        &app_data.yolo_project.config.export.class_map,
+    )));
+    commands.spawn(FilmstripUpdateNeeded);
+}
+
+/// Cycles the selected box's flag, or starts typing a comment on it.
+pub fn review_selected_box(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut review_manifest: ResMut<ReviewManifest>,
+    mut text_entry: ResMut<TextEntry>,
+    selected_bounding_box: Query<&BoundingBox, With<SelectedBoundingBox>>,
+    selected_image: Query<Entity, With<SelectedImage>>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+
+    let flag_box = keyboard_input.just_pressed(&key_map.flag_box);
+    let comment_box = keyboard_input.just_pressed(&key_map.comment_box);
+    if !flag_box && !comment_box {
+        return;
+    }
+
+    let entry = match selected_bounding_box.iter().next() {
+        Some(bounding_box) => bounding_box.index,
+        None => {
+            info!("Select a box to review it");
+            return;
+        }
+    };
+
+    if comment_box {
+        text_entry.start(TextEntryTarget::BoxComment { entry });
+        return;
+    }
+
+    let pair = match app_data.yolo_project.pair_at_index(app_data.index) {
+        Some(pair) => pair,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    let flag = review_manifest.cycle_box_flag(&pair.name, entry);
+    info!(
+        "Box {} in {} flagged {}",
+        entry,
+        pair.name,
+        flag.map_or("none", |flag| flag.describe())
+    );
+    save_review_manifest(&review_manifest, &app_data);
+    repaint_bounding_boxes(&mut commands, &selected_image, entry);
+}
+
+/// Saves comments typed for the current image or one of its boxes.
+pub fn apply_review_comment(
+    mut commands: Commands,
+    app_data: Res<AppData>,
+    mut text_entry: ResMut<TextEntry>,
+    mut review_manifest: ResMut<ReviewManifest>,
+    selected_image: Query<Entity, With<SelectedImage>>,
+) {
+    let (target, comment) = match text_entry.take_submitted() {
+        Some(submitted) => submitted,
+        None => return,
+    };
+
+    let pair = match app_data.yolo_project.pair_at_index(app_data.index) {
+        Some(pair) => pair,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    match target {
+        TextEntryTarget::ImageComment => review_manifest.add_comment(&pair.name, comment),
+        TextEntryTarget::BoxComment { entry } => {
+            review_manifest.add_box_comment(&pair.name, entry, comment);
+            repaint_bounding_boxes(&mut commands, &selected_image, entry);
+        }
+        // Taken by `handle_modal_keys` before `Update`.
+        TextEntryTarget::Command => return,
+    }
+    save_review_manifest(&review_manifest, &app_data);
+}
+
+/// Opens the image of the next flagged or commented box across the project
+/// and focuses the box.
+#[allow(clippy::too_many_arguments)]
+pub fn next_flagged_box(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    keyboard_input: KeyInput,
+    mut app_data: ResMut<AppData>,
+    review_manifest: Res<ReviewManifest>,
+    navigation_filter: Res<NavigationFilter>,
+    mut pending_box_focus: ResMut<PendingBoxFocus>,
+    mut last: Local<Option<(usize, usize)>>,
+    query_selected_images: Query<Entity, With<SelectedImage>>,
+    mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.next_flagged_box) {
+        return;
+    }
+
+    let marked_boxes = review_manifest.marked_boxes(&app_data.yolo_project.get_valid_pairs());
+    let (pair_index, entry) = match last
+        .and_then(|last| marked_boxes.iter().find(|marked| **marked > last))
+        .or(marked_boxes.first())
+    {
+        Some(marked) => *marked,
+        None => {
+            info!("No flagged boxes");
+            return;
+        }
+    };
+
+    *last = Some((pair_index, entry));
+    pending_box_focus.0 = Some((pair_index, entry));
+
+    let index_label =
+        navigation_filter.index_label(pair_index, &app_data.yolo_project.config.export.class_map);
+
+    if app_data.index as usize == pair_index {
+        commands.spawn(UiLabelingIndexUpdateNeeded(index_label));
+        return;
+    }
+
+    app_data.index = pair_index as isize;
+    replace_selected_image(
+        &mut commands,
+        &asset_server,
+        &app_data,
+        index_label,
+        &mut main_camera,
+        &query_selected_images,
+    );
+}
+
+/// Marks flagged and commented boxes at their top right corner.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_box_flags(
+    mut painter: ShapePainter,
+    app_data: Res<AppData>,
+    review_manifest: Res<ReviewManifest>,
+    ui: Res<Ui>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<&BoundingBox>,
+    main_camera: Query<&OrthographicProjection, With<MainCamera>>,
+) {
+    let image_size = match selected_image_size(&images, &selected_image) {
+        Some((_, image_size)) => image_size,
+        None => return,
+    };
+
+    let review = match app_data
+        .yolo_project
+        .pair_at_index(app_data.index)
+        .and_then(|pair| review_manifest.review(&pair.name).cloned())
+    {
+        Some(review) if !review.boxes.is_empty() => review,
+        _ => return,
+    };
+
+    let scale = main_camera
+        .iter()
+        .next()
+        .map_or(1.0, |projection| projection.scale);
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.hollow = false;
+
+    for bounding_box in bounding_boxes.iter() {
+        let box_review = match review.boxes.get(&bounding_box.index) {
+            Some(box_review) => box_review,
+            None => continue,
+        };
+
+        painter.color = match box_review.flag {
+            Some(BoxFlag::WrongClass) => Color::from(RED_500),
+            Some(BoxFlag::LooseFit) => Color::from(AMBER_500),
+            Some(BoxFlag::MissingNeighbour) => Color::from(SKY_500),
+            None => ui.colors.text,
+        };
+
+        let rect = bounding_box.pixel_rect();
+        let corner = Vec2::new(rect.max.x, rect.min.y);
+        painter.set_translation(image_pixel_to_world(corner, image_size).extend(FLAG_Z));
+        painter.circle(FLAG_RADIUS * scale);
+    }
+}
+
+/// Repaints the selected image's boxes so the left panel lists new marks.
+fn repaint_bounding_boxes(
+    commands: &mut Commands,
+    selected_image: &Query<Entity, With<SelectedImage>>,
+    select: usize,
+) {
+    for selected_image_eid in selected_image.iter() {
+        commands
+            .entity(selected_image_eid)
+            .despawn_descendants()
+            .remove::<ContainsBoundingBoxes>()
+            .insert(SelectAfterReload(select));
+    }
+}
+
+/// Shows the current image's review status and latest comment beside the index.
+pub fn update_review_badge(
+    app_data: Res<AppData>,
+    review_manifest: Res<ReviewManifest>,
+    ui: Res<Ui>,
+    mut badge: Query<(&mut Text, &mut TextColor), With<UiReviewBadge>>,
+    mut last_index: Local<Option<isize>>,
+) {
+    if !review_manifest.is_changed() && *last_index == Some(app_data.index) {
+        return;
+    }
+    *last_index = Some(app_data.index);
+
+    let review = app_data
+        .yolo_project
+        .pair_at_index(app_data.index)
+        .and_then(|pair| review_manifest.review(&pair.name).cloned())
+        .unwrap_or_default();
+
+    let color = match review.status {
+        ReviewStatus::Unreviewed => ui.colors.text,
+        ReviewStatus::Approved => Color::from(GREEN_500),
+        ReviewStatus::NeedsFix => Color::from(AMBER_500),
+    };
+
+    let label = match review.comments.last() {
+        Some(comment) if review.comments.len() > 1 => format!(
+            "{}: {} (+{})",
+            review.status.describe(),
+            comment,
+            review.comments.len() - 1
+        ),
+        Some(comment) => format!("{}: {}", review.status.describe(), comment),
+        None => review.status.describe().to_string(),
+    };
+
+    for (mut text, mut text_color) in badge.iter_mut() {
+        text.0 = label.clone();
+        text_color.0 = color;
+    }
+}
+
+pub fn save_review_manifest(review_manifest: &ReviewManifest, app_data: &AppData) {
+    if let Err(e) = review_manifest.save(&app_data.config.output_path) {
+        error!("Unable to save review manifest: {}", e);
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+
+use crate::{
+    filters::{ImageFilter, NavigationFilter},
+    resources::AppData,
+    session::SessionState,
+    FocusInViewport, ImageReady, MainCamera, SelectedImage,
+};
+
+/// Applies the restored session's camera once its image has been fitted,
+/// so the saved zoom wins over the automatic fit.
+pub fn restore_session_camera(
+    mut commands: Commands,
+    session: Option<Res<SessionState>>,
+    app_data: Res<AppData>,
+    ready_image: Query<&SelectedImage, (With<ImageReady>, Without<FocusInViewport>)>,
+    mut main_camera: Query<(&mut OrthographicProjection, &mut Transform), With<MainCamera>>,
+) {
+    let session = match session {
+        Some(session) => session,
+        None => return,
+    };
+
+    if ready_image.iter().count() == 0 {
+        return;
+    }
+
+    let current_image_path = app_data
+        .yolo_project
+        .pair_at_index(app_data.index)
+        .and_then(|pair| pair.image_path);
+
+    if current_image_path.as_ref() == Some(&session.image_path) {
+        info!("Restoring session camera");
+        let (mut projection, mut camera_transform) = main_camera.single_mut();
+        projection.scale = session.zoom;
+        camera_transform.translation = session
+            .camera_translation
+            .extend(camera_transform.translation.z);
+    }
+
+    commands.remove_resource::<SessionState>();
+}
+
+pub fn save_session_state(
+    app_data: Res<AppData>,
+    navigation_filter: Res<NavigationFilter>,
+    main_camera: Query<(&OrthographicProjection, &Transform), With<MainCamera>>,
+    pending_restore: Option<Res<SessionState>>,
+    mut last_saved: Local<Option<(isize, ImageFilter, Option<isize>)>>,
+) {
+    // Keep the previous session on disk until it has been restored.
+    if pending_restore.is_some() {
+        return;
+    }
+
+    let key = (
+        app_data.index,
+        navigation_filter.filter.clone(),
+        app_data.active_class,
+    );
+
+    if last_saved.as_ref() == Some(&key) {
+        return;
+    }
+
+    let (projection, camera_transform) = main_camera.single();
+    write_session_state(&app_data, &navigation_filter, projection, camera_transform);
+    *last_saved = Some(key);
+}
+
+pub fn save_session_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    app_data: Res<AppData>,
+    navigation_filter: Res<NavigationFilter>,
+    main_camera: Query<(&OrthographicProjection, &Transform), With<MainCamera>>,
+    pending_restore: Option<Res<SessionState>>,
+) {
+    if exit_events.read().next().is_none() || pending_restore.is_some() {
+        return;
+    }
+
+    if let Ok((projection, camera_transform)) = main_camera.get_single() {
+        write_session_state(&app_data, &navigation_filter, projection, camera_transform);
+    }
+}
+
+pub fn write_session_state(
+    app_data: &AppData,
+    navigation_filter: &NavigationFilter,
+    projection: &OrthographicProjection,
+    camera_transform: &Transform,
+) {
+    let image_path = match app_data
+        .yolo_project
+        .pair_at_index(app_data.index)
+        .and_then(|pair| pair.image_path)
+    {
+        Some(image_path) => image_path,
+        None => {
+            error!("Image path not found");
+            return;
+        }
+    };
+
+    let session = SessionState {
+        image_path,
+        zoom: projection.scale,
+        camera_translation: camera_transform.translation.truncate(),
+        filter: navigation_filter.filter.clone(),
+        active_class: app_data.active_class,
+    };
+
+    debug!("Saving session: {:?}", session);
+
+    if let Err(e) = session.save(&app_data.config.output_path) {
+        error!("Unable to save session state: {}", e);
+    }
+}
@@ -2,22 +2,80 @@ use bevy::prelude::*;
 
 use super::start_image_load;
 use crate::{
-    resources::AppData,
+    filters::{ImageFilter, NavigationFilter},
+    resources::{AppData, StartImage, StartupWarnings},
+    session::SessionState,
     settings::{MAIN_LAYER, UI_LAYER},
     utils::create_canvas_image,
     MainCamera, Ui, UiCamera, UninitializedRenderTarget,
 };
 
+pub fn log_startup_warnings(startup_warnings: Res<StartupWarnings>) {
+    for warning in startup_warnings.0.iter() {
+        warn!("{}", warning);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn setup(
     mut commands: Commands,
     mut app_data: ResMut<AppData>,
     mut ui: ResMut<Ui>,
     mut images: ResMut<Assets<Image>>,
+    mut navigation_filter: ResMut<NavigationFilter>,
+    session: Option<Res<SessionState>>,
+    start_image: Option<Res<StartImage>>,
     asset_server: Res<AssetServer>,
 ) {
-    app_data.index = 0;
     let valid_pairs = app_data.yolo_project.get_valid_pairs();
-    let selected_pair = valid_pairs[0].clone();
+
+    let opened_index = start_image.and_then(|start_image| {
+        let index = start_image.image_index(&valid_pairs);
+        if index.is_none() {
+            warn!("Image {:?} is not part of the project", start_image.0);
+        }
+        index
+    });
+
+    // Open the requested image, otherwise resume at the last viewed image
+    // when it is still part of the project.
+    let resume_index = match (opened_index, session) {
+        (Some(index), session) => {
+            // The session's camera belongs to its own image.
+            if session.is_some() {
+                commands.remove_resource::<SessionState>();
+            }
+            index
+        }
+        (None, Some(session)) => match session.image_index(&valid_pairs) {
+            Some(index) => index,
+            None => {
+                warn!(
+                    "Last viewed image {:?} no longer exists; starting from the first image",
+                    session.image_path
+                );
+                commands.remove_resource::<SessionState>();
+                0
+            }
+        },
+        (None, None) => 0,
+    };
+
+    // An image opened by name is shown even if the restored filter hides it.
+    if let Some(index) = opened_index {
+        if !navigation_filter.contains(index) {
+            let class_map = &app_data.yolo_project.config.export.class_map;
+            info!(
+                "Image {:?} is outside the navigation filter {}; showing all images",
+                valid_pairs[index].image_path,
+                navigation_filter.filter.describe(class_map)
+            );
+            navigation_filter.set_filter(ImageFilter::All, &valid_pairs);
+        }
+    }
+
+    app_data.index = navigation_filter.nearest(resume_index).unwrap_or(0) as isize;
+    let selected_pair = valid_pairs[app_data.index as usize].clone();
 
     let first_image = selected_pair.clone().image_path.unwrap();
     let first_image_path = first_image.as_path().to_string_lossy().into_owned();
@@ -55,17 +113,44 @@ pub fn setup(
         UiCamera,
     ));
 
-    let (container_ui_eid, left_panel_ui_eid) = ui.spawn_ui(&mut commands, &canvas_image_handle);
+    let (container_ui_eid, left_panel_ui_eid, canvas_panel_ui_eid) =
+        ui.spawn_ui(&mut commands, &canvas_image_handle);
+
+    let minimap = &app_data.config.settings.minimap;
+    if minimap.enabled {
+        ui.spawn_minimap(&mut commands, canvas_panel_ui_eid, minimap.width);
+    }
+
+    ui.spawn_stats_panel(&mut commands, canvas_panel_ui_eid);
+    ui.spawn_help_overlay(&mut commands, canvas_panel_ui_eid);
+
+    if app_data.config.settings.predictions.labels.is_some() {
+        ui.spawn_comparison_summary(&mut commands, left_panel_ui_eid);
+    }
+
+    let filmstrip = &app_data.config.settings.filmstrip;
+    if filmstrip.enabled {
+        ui.spawn_filmstrip(
+            &mut commands,
+            container_ui_eid,
+            filmstrip.neighbours,
+            filmstrip.thumbnail_size,
+        );
+    }
 
     app_data.ui_eid = Some(container_ui_eid);
     app_data.left_panel_eid = Some(left_panel_ui_eid);
 
+    let index_label = navigation_filter.index_label(
+        app_data.index as usize,
+        &app_data.yolo_project.config.export.class_map,
+    );
+
     start_image_load(
         &mut commands,
-        asset_server,
+        &asset_server,
         app_data.index,
-        valid_pairs.len() as isize - 1,
-        0.0,
+        index_label,
         valid_pairs,
     );
 }
@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+
+use crate::{
+    bounding_boxes::{BoundingBox, SelectedBoundingBox},
+    image_cache::ImageCache,
+    key_bindings::KeyInput,
+    labels::pixel_rect_to_entry,
+    resources::AppData,
+    shrink::shrink_to_content,
+    utils::rgba8_data,
+    LabelEditNeeded, SelectedImage,
+};
+
+use super::current_label_file;
+
+/// Tightens the selected box, or with the bulk key every box on the image,
+/// to the content inside it.
+#[allow(clippy::too_many_arguments)]
+pub fn shrink_bounding_boxes(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<(&BoundingBox, Has<SelectedBoundingBox>)>,
+) {
+    let key_map = &app_data.config.settings.key_map;
+    let bulk = keyboard_input.just_pressed(&key_map.shrink_all_boxes);
+    if !bulk && !keyboard_input.just_pressed(&key_map.shrink_box) {
+        return;
+    }
+
+    let targets = bounding_boxes
+        .iter()
+        .filter(|(_, selected)| bulk || *selected)
+        .collect::<Vec<(&BoundingBox, bool)>>();
+
+    if targets.is_empty() {
+        info!("No bounding box selected to shrink");
+        return;
+    }
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    let image = match images.get(&sprite.image) {
+        Some(image) => image,
+        None => {
+            error!("Image not found");
+            return;
+        }
+    };
+
+    let rgba = match rgba8_data(image) {
+        Some(rgba) => rgba,
+        None => {
+            error!(
+                "Shrink to fit needs an 8-bit RGBA image, found {:?}",
+                image.texture_descriptor.format
+            );
+            return;
+        }
+    };
+
+    let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+        Some(label_file) => label_file,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    let image_size = Vec2::new(image.width() as f32, image.height() as f32);
+    let settings = &app_data.config.settings.shrink;
+    let mut select = None;
+
+    for (bounding_box, selected) in targets {
+        if selected {
+            select = Some(bounding_box.index);
+        }
+
+        let shrunk = match shrink_to_content(
+            rgba,
+            image.width(),
+            image.height(),
+            bounding_box.pixel_rect(),
+            settings,
+        ) {
+            Some(shrunk) => shrunk,
+            None => {
+                info!("Bounding box {} holds no content", bounding_box.index);
+                continue;
+            }
+        };
+
+        match label_file.entries.get_mut(bounding_box.index) {
+            Some(entry) => *entry = pixel_rect_to_entry(entry.class, shrunk, image_size),
+            None => error!("Label entry {} not found", bounding_box.index),
+        }
+    }
+
+    commands.spawn(LabelEditNeeded { label_file, select });
+}
@@ -0,0 +1,292 @@
+use bevy::prelude::*;
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bounding_boxes::BoundingBox,
+    image_cache::ImageCache,
+    key_bindings::KeyInput,
+    labels::pixel_rect_to_entry,
+    resources::AppData,
+    settings::MAIN_LAYER,
+    sprite_grid::{has_content, SpriteGrid, SpriteGridPreview},
+    suggestions::overlaps_existing,
+    utils::{image_pixel_to_world, rgba8_data},
+    LabelEditNeeded, SelectedImage, Ui,
+};
+
+use super::{current_label_file, default_class};
+
+const GRID_Z: f32 = 450.0;
+
+/// Shows the configured sprite grid, or one detected from the image, over
+/// the selected image. Pressing the key again hides it.
+pub fn toggle_sprite_grid_preview(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    images: Res<Assets<Image>>,
+    mut preview: ResMut<SpriteGridPreview>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.toggle_sprite_grid) {
+        return;
+    }
+
+    if preview.grid.is_some() {
+        *preview = SpriteGridPreview::default();
+        return;
+    }
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    let image = match images.get(&sprite.image) {
+        Some(image) => image,
+        None => {
+            error!("Image not found");
+            return;
+        }
+    };
+
+    let settings = &app_data.config.settings.sprite_grid;
+    let grid = match settings.grid {
+        Some(grid) => grid,
+        None => {
+            let rgba = match rgba8_data(image) {
+                Some(rgba) => rgba,
+                None => {
+                    error!(
+                        "Grid detection needs an 8-bit RGBA image, found {:?}",
+                        image.texture_descriptor.format
+                    );
+                    return;
+                }
+            };
+
+            match SpriteGrid::detect(
+                rgba,
+                image.width(),
+                image.height(),
+                settings.alpha_threshold,
+                (settings.frame_width, settings.frame_height),
+            ) {
+                Some(grid) => grid,
+                None => {
+                    info!("No sprite grid detected; the image has no opaque pixels");
+                    return;
+                }
+            }
+        }
+    };
+
+    info!(
+        "Sprite grid: {} columns x {} rows of {}x{} cells",
+        grid.columns, grid.rows, grid.cell_width, grid.cell_height
+    );
+
+    *preview = SpriteGridPreview {
+        image: Some(sprite.image.id()),
+        grid: Some(grid),
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GridLabeling {
+    Cells,
+    Rows,
+    Columns,
+}
+
+/// Creates one box per non-empty cell, or one per animation row or column
+/// spanning its non-empty cells, from the previewed grid.
+#[allow(clippy::too_many_arguments)]
+pub fn label_sprite_grid(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    images: Res<Assets<Image>>,
+    mut preview: ResMut<SpriteGridPreview>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<&BoundingBox>,
+) {
+    let grid = match preview.grid {
+        Some(grid) => grid,
+        None => return,
+    };
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    // The preview belongs to the image it was detected on.
+    if preview.image != Some(sprite.image.id()) {
+        *preview = SpriteGridPreview::default();
+        return;
+    }
+
+    let key_map = &app_data.config.settings.key_map;
+    let labeling = [
+        (&key_map.label_grid_cells, GridLabeling::Cells),
+        (&key_map.label_grid_rows, GridLabeling::Rows),
+        (&key_map.label_grid_columns, GridLabeling::Columns),
+    ]
+    .into_iter()
+    .find(|(key, _)| keyboard_input.just_pressed(key))
+    .map(|(_, labeling)| labeling);
+
+    let labeling = match labeling {
+        Some(labeling) => labeling,
+        None => return,
+    };
+
+    let image = match images.get(&sprite.image) {
+        Some(image) => image,
+        None => return,
+    };
+
+    let rgba = match rgba8_data(image) {
+        Some(rgba) => rgba,
+        None => {
+            error!(
+                "Grid labeling needs an 8-bit RGBA image, found {:?}",
+                image.texture_descriptor.format
+            );
+            return;
+        }
+    };
+
+    let settings = &app_data.config.settings.sprite_grid;
+    let class_map = &app_data.yolo_project.config.export.class_map;
+    let class_named = |name: &str| {
+        let class = class_map
+            .iter()
+            .find(|(_, class_name)| class_name.as_str() == name)
+            .map(|(id, _)| *id);
+        if class.is_none() {
+            error!("Class '{}' not found in the class map", name);
+        }
+        class
+    };
+
+    let occupied = |column: u32, row: u32| {
+        has_content(
+            rgba,
+            image.width(),
+            image.height(),
+            grid.cell_rect(column, row),
+            settings.alpha_threshold,
+        )
+    };
+
+    // Spans the non-empty cells among `cells`, if there are any.
+    let span = |cells: Vec<(u32, u32)>| {
+        cells
+            .into_iter()
+            .filter(|(column, row)| occupied(*column, *row))
+            .map(|(column, row)| grid.cell_rect(column, row))
+            .reduce(|a, b| a.union(b))
+    };
+
+    let (class, rects) = match labeling {
+        GridLabeling::Cells => (
+            default_class(&app_data),
+            (0..grid.rows)
+                .flat_map(|row| (0..grid.columns).map(move |column| (column, row)))
+                .filter(|(column, row)| occupied(*column, *row))
+                .map(|(column, row)| grid.cell_rect(column, row))
+                .collect::<Vec<Rect>>(),
+        ),
+        GridLabeling::Rows => match class_named(&settings.row_class) {
+            Some(class) => (
+                class,
+                (0..grid.rows)
+                    .filter_map(|row| span((0..grid.columns).map(|column| (column, row)).collect()))
+                    .collect(),
+            ),
+            None => return,
+        },
+        GridLabeling::Columns => match class_named(&settings.column_class) {
+            Some(class) => (
+                class,
+                (0..grid.columns)
+                    .filter_map(|column| span((0..grid.rows).map(|row| (column, row)).collect()))
+                    .collect(),
+            ),
+            None => return,
+        },
+    };
+
+    let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+        Some(label_file) => label_file,
+        None => {
+            error!("Pair not found");
+            return;
+        }
+    };
+
+    let image_size = Vec2::new(image.width() as f32, image.height() as f32);
+    let max_overlap = app_data.config.settings.suggestions.max_overlap;
+    let existing = bounding_boxes
+        .iter()
+        .map(BoundingBox::pixel_rect)
+        .collect::<Vec<Rect>>();
+
+    let new_rects = rects
+        .into_iter()
+        .filter(|rect| !overlaps_existing(*rect, &existing, max_overlap))
+        .collect::<Vec<Rect>>();
+
+    info!("Labeling {} boxes from the sprite grid", new_rects.len());
+
+    label_file.entries.extend(
+        new_rects
+            .into_iter()
+            .map(|rect| pixel_rect_to_entry(class, rect, image_size)),
+    );
+
+    commands.spawn(LabelEditNeeded {
+        label_file,
+        select: None,
+    });
+
+    *preview = SpriteGridPreview::default();
+}
+
+pub fn draw_sprite_grid(
+    mut painter: ShapePainter,
+    preview: Res<SpriteGridPreview>,
+    ui: Res<Ui>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+) {
+    let grid = match preview.grid {
+        Some(grid) => grid,
+        None => return,
+    };
+
+    let image_size = match selected_image
+        .iter()
+        .next()
+        .and_then(|sprite| images.get(&sprite.image))
+    {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.thickness = 1.0;
+    painter.hollow = true;
+    painter.color = ui.colors.outer_border.with_alpha(0.8);
+
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let rect = grid.cell_rect(column, row);
+            painter.set_translation(image_pixel_to_world(rect.center(), image_size).extend(GRID_Z));
+            painter.rect(rect.size());
+        }
+    }
+}
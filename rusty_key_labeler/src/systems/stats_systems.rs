@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::{
+    bounding_boxes::BoundingBoxPainter,
+    key_bindings::KeyInput,
+    resources::AppData,
+    stats::{read_image_size, DatasetStats, Histogram, ImageSizeCache},
+    StatsUpdateNeeded, Ui, UiStatsPanel,
+};
+
+const STATS_FONT_SCALE: f32 = 0.7;
+const STATS_LABEL_WIDTH: f32 = 120.0;
+const STATS_BAR_HEIGHT: f32 = 10.0;
+
+pub fn toggle_stats_panel(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    mut stats_panel: Query<&mut Visibility, With<UiStatsPanel>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.toggle_stats) {
+        return;
+    }
+
+    for mut visibility in stats_panel.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => {
+                commands.spawn(StatsUpdateNeeded);
+                Visibility::Inherited
+            }
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Rebuilds the stats panel from the project's current labels while it is shown.
+pub fn update_stats_panel(
+    mut commands: Commands,
+    app_data: Res<AppData>,
+    ui: Res<Ui>,
+    bounding_box_painter: Res<BoundingBoxPainter>,
+    update_query: Query<Entity, With<StatsUpdateNeeded>>,
+    stats_panel: Query<(Entity, &Visibility), With<UiStatsPanel>>,
+    mut image_sizes: Local<ImageSizeCache>,
+) {
+    if update_query.is_empty() {
+        return;
+    }
+
+    for update_eid in update_query.iter() {
+        commands.entity(update_eid).despawn();
+    }
+
+    let (stats_panel_eid, visibility) = match stats_panel.get_single() {
+        Ok(stats_panel) => stats_panel,
+        Err(_) => return,
+    };
+
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    let class_map = &app_data.yolo_project.config.export.class_map;
+    let stats = DatasetStats::new(&app_data.yolo_project.data.pairs, class_map, |path| {
+        *image_sizes
+            .entry(path.to_path_buf())
+            .or_insert_with(|| read_image_size(path))
+    });
+
+    let class_colors = &bounding_box_painter.bounding_box_settings.class_color_map;
+    let max_boxes = stats
+        .classes
+        .iter()
+        .map(|class| class.boxes)
+        .max()
+        .unwrap_or(0);
+
+    commands
+        .entity(stats_panel_eid)
+        .despawn_descendants()
+        .with_children(|panel| {
+            panel.spawn(stats_text(
+                &ui,
+                format!(
+                    "{} images, {} unlabeled, {} boxes",
+                    stats.images, stats.unlabeled_images, stats.boxes
+                ),
+                ui.colors.text,
+            ));
+
+            panel.spawn(stats_text(
+                &ui,
+                "Boxes per class".to_string(),
+                ui.colors.inner_border,
+            ));
+            for class in stats.classes.iter() {
+                let color = class_colors
+                    .get(class.class as usize)
+                    .map_or(ui.colors.text, |color| Color::from(*color));
+                spawn_bar(
+                    panel,
+                    &ui,
+                    &class.name,
+                    format!("{} in {} img", class.boxes, class.images),
+                    class.boxes as f32 / max_boxes.max(1) as f32,
+                    color,
+                );
+            }
+
+            for (title, histogram) in [
+                ("Box size (px)", &stats.box_sizes),
+                ("Aspect ratio (w/h)", &stats.aspect_ratios),
+                ("Boxes per image", &stats.boxes_per_image),
+            ] {
+                panel.spawn(stats_text(&ui, title.to_string(), ui.colors.inner_border));
+                spawn_histogram(panel, &ui, histogram);
+            }
+
+            if stats.unsized_boxes > 0 {
+                panel.spawn(stats_text(
+                    &ui,
+                    format!("{} boxes in unreadable images", stats.unsized_boxes),
+                    ui.colors.text,
+                ));
+            }
+        });
+}
+
+fn spawn_histogram(panel: &mut ChildBuilder, ui: &Ui, histogram: &Histogram) {
+    let max_count = histogram.max_count().max(1);
+
+    for bucket in histogram.buckets.iter() {
+        spawn_bar(
+            panel,
+            ui,
+            &bucket.label,
+            bucket.count.to_string(),
+            bucket.count as f32 / max_count as f32,
+            ui.colors.outer_border,
+        );
+    }
+}
+
+/// A row with a label, a bar filled to `fraction` of its track, and a value.
+fn spawn_bar(
+    panel: &mut ChildBuilder,
+    ui: &Ui,
+    label: &str,
+    value: String,
+    fraction: f32,
+    color: Color,
+) {
+    panel
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                Node {
+                    width: Val::Px(STATS_LABEL_WIDTH),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                stats_text(ui, label.to_string(), ui.colors.text),
+            ));
+            row.spawn(Node {
+                flex_grow: 1.0,
+                height: Val::Px(STATS_BAR_HEIGHT),
+                ..default()
+            })
+            .with_children(|track| {
+                track.spawn((
+                    Node {
+                        width: Val::Percent(fraction * 100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(color),
+                ));
+            });
+            row.spawn(stats_text(ui, value, ui.colors.text));
+        });
+}
+
+fn stats_text(ui: &Ui, text: String, color: Color) -> (Text, TextFont, TextColor) {
+    (
+        Text::new(text),
+        TextFont {
+            font: ui.font_handle.clone().unwrap(),
+            font_size: ui.font_size * STATS_FONT_SCALE,
+            ..Default::default()
+        },
+        TextColor::from(color),
+    )
+}
@@ -0,0 +1,271 @@
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future::poll_once, AsyncComputeTaskPool},
+};
+use bevy_vector_shapes::prelude::*;
+
+use crate::{
+    bounding_boxes::BoundingBox,
+    image_cache::ImageCache,
+    key_bindings::KeyInput,
+    labels::pixel_rect_to_entry,
+    resources::AppData,
+    settings::MAIN_LAYER,
+    suggestions::{
+        alpha_component_rects, overlaps_existing, BoxProposal, BoxProposals, SuggestionTasks,
+    },
+    utils::{image_pixel_to_world, rgba8_data},
+    LabelEditNeeded, MainCamera, SelectedImage, Ui,
+};
+
+use super::{current_label_file, default_class};
+
+const PROPOSAL_Z: f32 = 550.0;
+
+/// Run condition keeping Tab and Space for reviewing proposals while any are pending.
+pub fn no_pending_proposals(proposals: Res<BoxProposals>) -> bool {
+    !proposals.is_pending()
+}
+
+/// Starts suggesting boxes around the opaque regions of the selected image.
+/// Pressing the key again while proposals are pending dismisses them.
+pub fn suggest_boxes(
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    images: Res<Assets<Image>>,
+    mut proposals: ResMut<BoxProposals>,
+    mut tasks: ResMut<SuggestionTasks>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+) {
+    if !keyboard_input.just_pressed(&app_data.config.settings.key_map.suggest_boxes) {
+        return;
+    }
+
+    if proposals.is_pending() {
+        info!("Dismissed {} suggested boxes", proposals.proposals.len());
+        *proposals = BoxProposals::default();
+        return;
+    }
+
+    if tasks.pending.is_some() {
+        info!("Still suggesting boxes");
+        return;
+    }
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    let image = match images.get(&sprite.image) {
+        Some(image) => image,
+        None => {
+            error!("Image not found");
+            return;
+        }
+    };
+
+    let rgba = match rgba8_data(image) {
+        Some(rgba) => rgba,
+        None => {
+            error!(
+                "Box suggestions need an 8-bit RGBA image, found {:?}",
+                image.texture_descriptor.format
+            );
+            return;
+        }
+    };
+
+    let settings = app_data.config.settings.suggestions.clone();
+    let rgba = rgba.to_vec();
+    let (width, height) = (image.width(), image.height());
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        alpha_component_rects(
+            &rgba,
+            width,
+            height,
+            settings.alpha_threshold,
+            settings.merge_gap,
+        )
+        .into_iter()
+        .filter(|rect| rect.width() >= settings.min_size && rect.height() >= settings.min_size)
+        .collect::<Vec<Rect>>()
+    });
+
+    tasks.pending = Some((sprite.image.id(), task));
+}
+
+/// Turns finished suggestions for the current image into pending proposals,
+/// skipping ones that overlap existing boxes.
+pub fn receive_suggestions(
+    app_data: Res<AppData>,
+    images: Res<Assets<Image>>,
+    mut tasks: ResMut<SuggestionTasks>,
+    mut proposals: ResMut<BoxProposals>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    bounding_boxes: Query<&BoundingBox>,
+    mut main_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    let (image, rects) = match tasks.bypass_change_detection().pending.as_mut() {
+        Some((image, task)) => match block_on(poll_once(task)) {
+            Some(rects) => (*image, rects),
+            None => return,
+        },
+        None => return,
+    };
+
+    tasks.pending = None;
+
+    // Suggestions for an image navigated away from are dropped.
+    let sprite = match selected_image
+        .iter()
+        .next()
+        .filter(|sprite| sprite.image.id() == image)
+    {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    let image_size = match images.get(&sprite.image) {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let max_overlap = app_data.config.settings.suggestions.max_overlap;
+    let existing = bounding_boxes
+        .iter()
+        .map(BoundingBox::pixel_rect)
+        .collect::<Vec<Rect>>();
+
+    let suggested = rects
+        .into_iter()
+        .filter(|rect| !overlaps_existing(*rect, &existing, max_overlap))
+        .map(BoxProposal::from_rect)
+        .collect::<Vec<BoxProposal>>();
+
+    info!("Suggested {} boxes", suggested.len());
+
+    *proposals = BoxProposals {
+        image: Some(image),
+        proposals: suggested,
+    };
+
+    center_on_current_proposal(&proposals, image_size, &mut main_camera);
+}
+
+/// Accepts the current proposal with the cycle selection key or rejects it
+/// with the change selection key, then moves on to the next one.
+#[allow(clippy::too_many_arguments)]
+pub fn review_box_proposals(
+    mut commands: Commands,
+    keyboard_input: KeyInput,
+    app_data: Res<AppData>,
+    image_cache: Res<ImageCache>,
+    images: Res<Assets<Image>>,
+    mut proposals: ResMut<BoxProposals>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+    mut main_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !proposals.is_pending() {
+        return;
+    }
+
+    let sprite = match selected_image.iter().next() {
+        Some(sprite) => sprite,
+        None => return,
+    };
+
+    // Proposals belong to the image they were computed for.
+    if proposals.image != Some(sprite.image.id()) {
+        *proposals = BoxProposals::default();
+        return;
+    }
+
+    let image_size = match images.get(&sprite.image) {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    let key_map = &app_data.config.settings.key_map;
+
+    if keyboard_input.just_pressed(&key_map.cycle_selection) {
+        let mut label_file = match current_label_file(&app_data, &image_cache, sprite) {
+            Some(label_file) => label_file,
+            None => {
+                error!("Pair not found");
+                return;
+            }
+        };
+
+        let proposal = proposals.proposals.remove(0);
+        label_file.entries.push(pixel_rect_to_entry(
+            proposal.class.unwrap_or_else(|| default_class(&app_data)),
+            proposal.rect,
+            image_size,
+        ));
+
+        commands.spawn(LabelEditNeeded {
+            label_file,
+            select: None,
+        });
+    } else if keyboard_input.just_released(&key_map.change_selection) {
+        proposals.proposals.remove(0);
+    } else {
+        return;
+    }
+
+    center_on_current_proposal(&proposals, image_size, &mut main_camera);
+}
+
+pub fn center_on_current_proposal(
+    proposals: &BoxProposals,
+    image_size: Vec2,
+    main_camera: &mut Query<&mut Transform, With<MainCamera>>,
+) {
+    if let Some(proposal) = proposals.proposals.first() {
+        let center = image_pixel_to_world(proposal.rect.center(), image_size);
+        let mut camera_transform = main_camera.single_mut();
+        camera_transform.translation.x = center.x;
+        camera_transform.translation.y = center.y;
+    }
+}
+
+pub fn draw_box_proposals(
+    mut painter: ShapePainter,
+    proposals: Res<BoxProposals>,
+    ui: Res<Ui>,
+    images: Res<Assets<Image>>,
+    selected_image: Query<&Sprite, With<SelectedImage>>,
+) {
+    if !proposals.is_pending() {
+        return;
+    }
+
+    let image_size = match selected_image
+        .iter()
+        .next()
+        .and_then(|sprite| images.get(&sprite.image))
+    {
+        Some(image) => Vec2::new(image.width() as f32, image.height() as f32),
+        None => return,
+    };
+
+    painter.render_layers = Some(MAIN_LAYER);
+    painter.thickness_type = ThicknessType::Pixels;
+    painter.hollow = true;
+
+    // The proposal under review is drawn thicker than the ones queued behind
+    // it, and detector proposals fade with their confidence.
+    for (position, proposal) in proposals.proposals.iter().enumerate().rev() {
+        let current = position == 0;
+        let alpha = proposal.confidence.unwrap_or(1.0).clamp(0.2, 1.0);
+        painter.thickness = if current { 3.0 } else { 1.0 };
+        painter.color = match current {
+            true => ui.colors.inner_border.with_alpha(alpha),
+            false => ui.colors.outer_border.with_alpha(0.6 * alpha),
+        };
+        let rect = proposal.rect;
+        painter.set_translation(image_pixel_to_world(rect.center(), image_size).extend(PROPOSAL_Z));
+        painter.rect(rect.size());
+    }
+}
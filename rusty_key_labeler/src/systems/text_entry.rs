@@ -0,0 +1,88 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+
+use crate::{
+    resources::{TextEntry, TextEntryTarget},
+    TextEntryPrompt,
+};
+
+/// Types into the active text entry and hides the keys from every binding
+/// until Enter submits or Escape cancels. Runs in `PreUpdate` once input
+/// has been collected.
+pub fn capture_text_entry(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut text_entry: ResMut<TextEntry>,
+) {
+    let target = match text_entry.target {
+        Some(target) => target,
+        None => {
+            keyboard_events.clear();
+            return;
+        }
+    };
+
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let text = std::mem::take(&mut text_entry.text);
+                text_entry.target = None;
+                if !text.trim().is_empty() {
+                    text_entry.submitted = Some((target, text.trim().to_string()));
+                }
+                break;
+            }
+            Key::Escape => {
+                text_entry.target = None;
+                text_entry.text.clear();
+                break;
+            }
+            Key::Backspace => {
+                text_entry.text.pop();
+            }
+            Key::Space => text_entry.text.push(' '),
+            Key::Character(characters) => text_entry.text.push_str(characters),
+            _ => {}
+        }
+    }
+
+    keyboard_input.reset_all();
+}
+
+pub fn update_text_entry_prompt(
+    text_entry: Res<TextEntry>,
+    mut prompt: Query<(&mut Text, &mut Visibility), With<TextEntryPrompt>>,
+) {
+    if !text_entry.is_changed() {
+        return;
+    }
+
+    for (mut text, mut visibility) in prompt.iter_mut() {
+        match text_entry.target {
+            Some(target) => {
+                text.0 = match target {
+                    TextEntryTarget::ImageComment => format!(
+                        "Image comment: {}_  (Enter saves, Esc cancels)",
+                        text_entry.text
+                    ),
+                    TextEntryTarget::BoxComment { entry } => format!(
+                        "Box {} comment: {}_  (Enter saves, Esc cancels)",
+                        entry, text_entry.text
+                    ),
+                    TextEntryTarget::Command => format!(":{}_", text_entry.text),
+                };
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
@@ -3,13 +3,17 @@ use bevy_ui_views::{VStack, VStackContainerItem};
 
 use crate::{
     settings::{UiColors, UI_LAYER},
-    CurrentFileNameLabel, FileNameLabelUpdateNeeded, UIBottomPanel, UILeftPanel, UITopPanel,
-    UiBasePanel, UiLabelingIndex, UiLabelingIndexUpdateNeeded,
+    ComparisonSummary, CurrentFileNameLabel, CursorReadout, FileNameLabelUpdateNeeded,
+    FilmstripBadge, FilmstripThumbnail, MinimapViewportRect, ScrubPreview, ScrubPreviewFileName,
+    ScrubPreviewImage, ScrubPreviewUpdateNeeded, TextEntryPrompt, UIBottomPanel, UILeftPanel,
+    UITopPanel, UiBasePanel, UiColorRole, UiFilmstrip, UiHelpOverlay, UiLabelingIndex,
+    UiLabelingIndexUpdateNeeded, UiMinimap, UiModeIndicator, UiReviewBadge, UiStatsPanel, UiStyle,
 };
 use crate::{TopRightPanelUI, Ui};
 
 pub const UI_Z_INDEX: f32 = 99.0;
 pub const PADDING: f32 = 5.0;
+pub const STATS_PANEL_WIDTH: f32 = 360.0;
 
 // #[derive(Debug, Clone, Component)]
 // pub struct UiProperties;
@@ -41,6 +45,41 @@ pub fn update_current_file_name_label(
     }
 }
 
+pub fn update_scrub_preview(
+    mut commands: Commands,
+    update_query: Query<(Entity, &ScrubPreviewUpdateNeeded)>,
+    mut preview: Query<&mut Visibility, With<ScrubPreview>>,
+    mut preview_image: Query<(&mut ImageNode, &mut Node), With<ScrubPreviewImage>>,
+    mut preview_file_name: Query<&mut Text, With<ScrubPreviewFileName>>,
+) {
+    for (update_eid, update) in update_query.iter() {
+        for mut visibility in preview.iter_mut() {
+            *visibility = match update.file_name {
+                Some(_) => Visibility::Visible,
+                None => Visibility::Hidden,
+            };
+        }
+
+        for (mut image_node, mut node) in preview_image.iter_mut() {
+            match &update.thumbnail {
+                Some(thumbnail) => {
+                    image_node.image = thumbnail.clone();
+                    node.display = Display::Flex;
+                }
+                None => node.display = Display::None,
+            }
+        }
+
+        if let Some(file_name) = &update.file_name {
+            for mut text in preview_file_name.iter_mut() {
+                text.0 = file_name.clone();
+            }
+        }
+
+        commands.entity(update_eid).despawn();
+    }
+}
+
 impl Ui {
     pub fn new(colors: &UiColors, font_size: f32, font_path: &str) -> Self {
         Self {
@@ -51,11 +90,16 @@ impl Ui {
         }
     }
 
-    pub fn spawn_ui(&self, commands: &mut Commands, canvas: &Handle<Image>) -> (Entity, Entity) {
+    pub fn spawn_ui(
+        &self,
+        commands: &mut Commands,
+        canvas: &Handle<Image>,
+    ) -> (Entity, Entity, Entity) {
         // Spawn the UI Container
         let container_eid = commands
             .spawn((
                 Name::new("ui_container"),
+                UiStyle::default().with_border(Some(UiColorRole::OuterBorder)),
                 UiBasePanel,
                 Node {
                     flex_direction: FlexDirection::Column,
@@ -79,10 +123,13 @@ impl Ui {
         let top_half_panel = commands
             .spawn((
                 Name::new("top_half_panel"),
+                UiStyle::default().with_border(Some(UiColorRole::OuterBorder)),
                 Node {
                     flex_direction: FlexDirection::Row,
                     width: Val::Percent(100.0),
                     height: Val::Percent(90.0),
+                    // Allows the filmstrip to take its height from this panel.
+                    min_height: Val::Px(0.0),
                     border: UiRect::all(Val::Px(1.0)),
                     padding: UiRect {
                         left: Val::Px(0.0),
@@ -108,6 +155,7 @@ impl Ui {
         let left_panel_ui_eid = commands
             .spawn((
                 Name::new("left_ui_panel"),
+                UiStyle::default().with_background(Some(UiColorRole::Background)),
                 Node {
                     // flex_direction: FlexDirection::Column,
                     left: Val::Px(0.0),
@@ -152,6 +200,58 @@ impl Ui {
             .entity(top_half_panel)
             .add_child(right_top_panel_ui_eid);
 
+        // Shown over the canvas while arrow keys scrub through images.
+        let scrub_preview_eid = commands
+            .spawn((
+                Name::new("scrub_preview"),
+                UiStyle::panel(UiColorRole::InnerBorder),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(35.0),
+                    top: Val::Percent(30.0),
+                    width: Val::Percent(30.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    border: UiRect::all(Val::Px(1.0)),
+                    padding: UiRect::all(Val::Px(PADDING)),
+                    ..default()
+                },
+                BorderColor(self.colors.inner_border),
+                BackgroundColor(self.colors.background),
+                Visibility::Hidden,
+                ScrubPreview,
+                UI_LAYER,
+            ))
+            .with_children(|scrub_preview| {
+                scrub_preview.spawn((
+                    Name::new("scrub_preview_image"),
+                    ImageNode::default(),
+                    Node {
+                        width: Val::Percent(100.0),
+                        ..default()
+                    },
+                    ScrubPreviewImage,
+                ));
+
+                scrub_preview.spawn((
+                    Name::new("scrub_preview_file_name"),
+                    UiStyle::text(UiColorRole::Text, 1.0),
+                    Text::from(""),
+                    TextFont {
+                        font: self.font_handle.clone().unwrap(),
+                        font_size: self.font_size,
+                        ..Default::default()
+                    },
+                    TextColor::from(self.colors.text),
+                    ScrubPreviewFileName,
+                ));
+            })
+            .id();
+
+        commands
+            .entity(right_top_panel_ui_eid)
+            .add_child(scrub_preview_eid);
+
         let vstack_eid = commands
             .spawn((
                 Name::new("VStack"),
@@ -180,6 +280,7 @@ impl Ui {
             .spawn((
                 UIBottomPanel,
                 Name::new("bottom_ui_panel"),
+                UiStyle::panel(UiColorRole::OuterBorder),
                 Node {
                     flex_direction: FlexDirection::Column,
                     width: Val::Percent(100.0),
@@ -206,20 +307,74 @@ impl Ui {
                 ..default()
             })
             .with_children(|bottom_ui_panel| {
+                bottom_ui_panel
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(PADDING * 2.0),
+                        ..default()
+                    })
+                    .with_children(|index_row| {
+                        index_row.spawn((
+                            Name::new("mode_indicator"),
+                            UiStyle::text(UiColorRole::InnerBorder, 1.0),
+                            Text::from(""),
+                            TextFont {
+                                font: self.font_handle.clone().unwrap(),
+                                font_size: self.font_size,
+                                ..Default::default()
+                            },
+                            TextColor::from(self.colors.inner_border),
+                            UiModeIndicator,
+                        ));
+
+                        index_row.spawn((
+                            Name::new("labeling_index"),
+                            UiStyle::text(UiColorRole::Text, 1.0),
+                            Text::from("0/0"),
+                            TextFont {
+                                font: self.font_handle.clone().unwrap(),
+                                font_size: self.font_size,
+                                ..Default::default()
+                            },
+                            TextColor::from(self.colors.text),
+                            UiLabelingIndex,
+                        ));
+
+                        index_row.spawn((
+                            Name::new("review_badge"),
+                            UiStyle::text(UiColorRole::Text, 1.0),
+                            Text::from(""),
+                            TextFont {
+                                font: self.font_handle.clone().unwrap(),
+                                font_size: self.font_size,
+                                ..Default::default()
+                            },
+                            TextColor::from(self.colors.text),
+                            UiReviewBadge,
+                        ));
+                    });
+
                 bottom_ui_panel.spawn((
-                    Name::new("labeling_index"),
-                    Text::from("0/0"),
+                    Name::new("current_file_name"),
+                    UiStyle::text(UiColorRole::Text, 1.0),
+                    Text::from(""),
                     TextFont {
                         font: self.font_handle.clone().unwrap(),
                         font_size: self.font_size,
                         ..Default::default()
                     },
                     TextColor::from(self.colors.text),
-                    UiLabelingIndex,
+                    Node {
+                        min_height: Val::Px(20.0),
+                        min_width: Val::Px(100.0),
+                        ..Default::default()
+                    },
+                    CurrentFileNameLabel,
                 ));
 
                 bottom_ui_panel.spawn((
-                    Name::new("current_file_name"),
+                    Name::new("cursor_readout"),
+                    UiStyle::text(UiColorRole::Text, 1.0),
                     Text::from(""),
                     TextFont {
                         font: self.font_handle.clone().unwrap(),
@@ -227,20 +382,267 @@ impl Ui {
                         ..Default::default()
                     },
                     TextColor::from(self.colors.text),
-                    Node {
-                        min_height: Val::Px(20.0),
-                        min_width: Val::Px(100.0),
+                    CursorReadout,
+                ));
+
+                bottom_ui_panel.spawn((
+                    Name::new("text_entry_prompt"),
+                    UiStyle::text(UiColorRole::Text, 1.0),
+                    Text::from(""),
+                    TextFont {
+                        font: self.font_handle.clone().unwrap(),
+                        font_size: self.font_size,
                         ..Default::default()
                     },
-                    CurrentFileNameLabel,
+                    TextColor::from(self.colors.text),
+                    Visibility::Hidden,
+                    TextEntryPrompt,
                 ));
             })
             .id();
 
         commands.entity(container_eid).add_child(bottom_ui_eid);
 
-        // Return the container entity ID
-        (container_eid, left_panel_ui_eid)
+        // Return the container, left panel and canvas panel entity IDs
+        (container_eid, left_panel_ui_eid, right_top_panel_ui_eid)
+    }
+
+    /// Spawns a strip of thumbnails between the canvas and the bottom panel,
+    /// with the current image in the middle slot.
+    pub fn spawn_filmstrip(
+        &self,
+        commands: &mut Commands,
+        container_eid: Entity,
+        neighbours: usize,
+        thumbnail_size: u32,
+    ) -> Entity {
+        let neighbours = neighbours as isize;
+        let thumbnail_size = thumbnail_size as f32;
+
+        let filmstrip_eid = commands
+            .spawn((
+                Name::new("filmstrip"),
+                UiStyle::panel(UiColorRole::OuterBorder),
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::Center,
+                    width: Val::Percent(100.0),
+                    height: Val::Px(thumbnail_size + 2.0 * PADDING),
+                    flex_shrink: 0.0,
+                    overflow: Overflow::clip(),
+                    border: UiRect::horizontal(Val::Px(1.0)),
+                    ..default()
+                },
+                BorderColor(self.colors.outer_border),
+                BackgroundColor(self.colors.background),
+                UiFilmstrip,
+                UI_LAYER,
+            ))
+            .with_children(|filmstrip| {
+                for offset in -neighbours..=neighbours {
+                    let border_role = match offset {
+                        0 => UiColorRole::InnerBorder,
+                        _ => UiColorRole::OuterBorder,
+                    };
+
+                    filmstrip
+                        .spawn((
+                            Name::new(format!("filmstrip_slot_{}", offset)),
+                            UiStyle::default().with_border(Some(border_role)),
+                            Node {
+                                width: Val::Px(thumbnail_size),
+                                height: Val::Px(thumbnail_size),
+                                margin: UiRect::all(Val::Px(PADDING / 2.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                border: UiRect::all(Val::Px(1.0)),
+                                ..default()
+                            },
+                            BorderColor(border_role.color(&self.colors)),
+                        ))
+                        .with_children(|slot| {
+                            slot.spawn((
+                                ImageNode::default(),
+                                Node {
+                                    max_width: Val::Percent(100.0),
+                                    max_height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                FilmstripThumbnail { offset },
+                            ));
+
+                            slot.spawn((
+                                Text::from(""),
+                                TextFont {
+                                    font: self.font_handle.clone().unwrap(),
+                                    font_size: self.font_size * 0.75,
+                                    ..Default::default()
+                                },
+                                TextColor::from(self.colors.text),
+                                Node {
+                                    position_type: PositionType::Absolute,
+                                    left: Val::Px(2.0),
+                                    top: Val::Px(0.0),
+                                    ..default()
+                                },
+                                FilmstripBadge { offset },
+                                UiStyle::text(UiColorRole::Text, 0.75),
+                            ));
+                        });
+                }
+            })
+            .id();
+
+        // Sits between the top panel and the bottom panel.
+        commands
+            .entity(container_eid)
+            .insert_children(1, &[filmstrip_eid]);
+
+        filmstrip_eid
+    }
+
+    /// Spawns the label/prediction comparison along the bottom of the left panel.
+    pub fn spawn_comparison_summary(&self, commands: &mut Commands, left_panel_eid: Entity) {
+        let summary_eid = commands
+            .spawn((
+                Name::new("comparison_summary"),
+                UiStyle::text(UiColorRole::Text, 1.0)
+                    .with_background(Some(UiColorRole::Background))
+                    .with_border(Some(UiColorRole::OuterBorder)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                    max_height: Val::Percent(40.0),
+                    padding: UiRect::all(Val::Px(PADDING)),
+                    border: UiRect::top(Val::Px(1.0)),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                Text::from(""),
+                TextFont {
+                    font: self.font_handle.clone().unwrap(),
+                    font_size: self.font_size,
+                    ..Default::default()
+                },
+                TextColor::from(self.colors.text),
+                BorderColor(self.colors.outer_border),
+                BackgroundColor(self.colors.background),
+                ZIndex(1),
+                ComparisonSummary,
+                UI_LAYER,
+            ))
+            .id();
+
+        commands.entity(left_panel_eid).add_child(summary_eid);
+    }
+
+    /// Spawns the hidden dataset stats panel in the top-left corner of the canvas panel.
+    pub fn spawn_stats_panel(&self, commands: &mut Commands, canvas_panel_eid: Entity) {
+        let stats_panel_eid = commands
+            .spawn((
+                Name::new("stats_panel"),
+                UiStyle::panel(UiColorRole::OuterBorder),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(PADDING),
+                    top: Val::Px(PADDING),
+                    width: Val::Px(STATS_PANEL_WIDTH),
+                    max_height: Val::Percent(90.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    padding: UiRect::all(Val::Px(PADDING)),
+                    border: UiRect::all(Val::Px(1.0)),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BorderColor(self.colors.outer_border),
+                BackgroundColor(self.colors.background),
+                Visibility::Hidden,
+                ZIndex(2),
+                UiStatsPanel,
+                UI_LAYER,
+            ))
+            .id();
+
+        commands.entity(canvas_panel_eid).add_child(stats_panel_eid);
+    }
+
+    /// Spawns the hidden key binding help overlay over the canvas panel.
+    pub fn spawn_help_overlay(&self, commands: &mut Commands, canvas_panel_eid: Entity) {
+        let help_overlay_eid = commands
+            .spawn((
+                Name::new("help_overlay"),
+                UiStyle::panel(UiColorRole::OuterBorder),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(5.0),
+                    top: Val::Percent(5.0),
+                    width: Val::Percent(90.0),
+                    max_height: Val::Percent(90.0),
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    align_content: AlignContent::FlexStart,
+                    column_gap: Val::Px(PADDING * 4.0),
+                    row_gap: Val::Px(PADDING * 2.0),
+                    padding: UiRect::all(Val::Px(PADDING)),
+                    border: UiRect::all(Val::Px(1.0)),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BorderColor(self.colors.outer_border),
+                BackgroundColor(self.colors.background),
+                Visibility::Hidden,
+                ZIndex(3),
+                UiHelpOverlay,
+                UI_LAYER,
+            ))
+            .id();
+
+        commands
+            .entity(canvas_panel_eid)
+            .add_child(help_overlay_eid);
+    }
+
+    /// Spawns the minimap in the bottom-right corner of the canvas panel.
+    pub fn spawn_minimap(&self, commands: &mut Commands, canvas_panel_eid: Entity, width: f32) {
+        let minimap_eid = commands
+            .spawn((
+                Name::new("minimap"),
+                UiStyle::panel(UiColorRole::OuterBorder),
+                Node {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(PADDING),
+                    bottom: Val::Px(PADDING),
+                    width: Val::Px(width),
+                    border: UiRect::all(Val::Px(1.0)),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                ImageNode::default(),
+                BorderColor(self.colors.outer_border),
+                BackgroundColor(self.colors.background),
+                UiMinimap,
+                UI_LAYER,
+            ))
+            .with_children(|minimap| {
+                minimap.spawn((
+                    Name::new("minimap_viewport"),
+                    UiStyle::default().with_border(Some(UiColorRole::InnerBorder)),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    BorderColor(self.colors.inner_border),
+                    ZIndex(1),
+                    MinimapViewportRect,
+                ));
+            })
+            .id();
+
+        commands.entity(canvas_panel_eid).add_child(minimap_eid);
     }
 
     pub fn create_bounding_box_entry(
@@ -5,21 +5,20 @@ use crate::utils::create_canvas_image;
 use crate::{AppData, CenterInViewport, MainCamera};
 use crate::{ComputedViewport, FocusInViewport, TopRightPanelUI, UninitializedRenderTarget};
 
+/// Sizes the main camera's render target to the canvas panel, on startup and
+/// again whenever the panel is resized.
 pub fn compute_viewport(
     mut commands: Commands,
-    mut main_camera: Query<&mut Camera, With<MainCamera>>,
+    mut main_camera: Query<(&mut Camera, &mut OrthographicProjection), With<MainCamera>>,
     mut images: ResMut<Assets<Image>>,
     mut viewport: Query<
         (Entity, &ComputedNode, &mut ImageNode, &GlobalTransform),
         With<TopRightPanelUI>,
     >,
+    computed_viewport: Query<&ComputedViewport>,
     uninitialized_render_target: Query<Entity, With<UninitializedRenderTarget>>,
 ) {
-    if uninitialized_render_target.iter().count() == 0 {
-        return;
-    }
-
-    let mut main_camera = match main_camera.iter_mut().next() {
+    let (mut main_camera, mut projection) = match main_camera.iter_mut().next() {
         Some(camera) => camera,
         None => {
             error!("Main camera not found");
@@ -46,16 +45,20 @@ pub fn compute_viewport(
             }
         };
 
-    let uninitialized_render_target_eid = match uninitialized_render_target.iter().next() {
-        Some(eid) => eid,
-        None => {
-            error!("UninitializedRenderTarget not found");
-            return;
-        }
-    };
-
     let computed_viewport_size = viewport_computed_node.size();
 
+    // Compare whole pixels; the canvas cannot be resized by fractions.
+    let previous_size = computed_viewport
+        .iter()
+        .next()
+        .map(|viewport| Vec2::new(viewport.width, viewport.height));
+    let resized = previous_size
+        .is_some_and(|previous_size| previous_size.as_uvec2() != computed_viewport_size.as_uvec2());
+
+    if uninitialized_render_target.iter().count() == 0 && !resized {
+        return;
+    }
+
     if computed_viewport_size.x <= 0.0 || computed_viewport_size.y <= 0.0 {
         error!("Viewport size is invalid");
         return;
@@ -71,6 +74,17 @@ pub fn compute_viewport(
     viewport_image_node.image = canvas_image_handle.clone();
     main_camera.target = RenderTarget::Image(viewport_image_node.image.clone());
 
+    // Keep the same part of the world in view, so a fitted image stays fitted
+    // and a zoomed-in region stays zoomed in.
+    if let Some(previous_size) = previous_size.filter(|_| resized) {
+        let ratio = previous_size / computed_viewport_size;
+        projection.scale *= ratio.x.max(ratio.y);
+        debug!(
+            "Viewport resized from {:?} to {:?}",
+            previous_size, computed_viewport_size
+        );
+    }
+
     let computed_viewport = ComputedViewport {
         width: computed_viewport_size.x,
         height: computed_viewport_size.y,
@@ -78,7 +92,10 @@ pub fn compute_viewport(
     };
 
     commands.entity(viewport_eid).insert(computed_viewport);
-    commands.entity(uninitialized_render_target_eid).despawn();
+
+    for uninitialized_render_target_eid in uninitialized_render_target.iter() {
+        commands.entity(uninitialized_render_target_eid).despawn();
+    }
 }
 
 pub fn fit_to_viewport(
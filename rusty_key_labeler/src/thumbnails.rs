@@ -0,0 +1,353 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use bevy::{
+    asset::{Handle, RenderAssetUsages},
+    color::{ColorToPacked, Srgba},
+    image::Image,
+    prelude::Resource,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    tasks::Task,
+};
+use hashbrown::{HashMap, HashSet};
+use image::RgbaImage;
+use yolo_io::{ImageLabelPair, YoloEntry};
+
+use crate::review::ReviewStatus;
+
+/// Downscaled previews of project images, generated off the main thread and
+/// cached on disk next to the session state.
+#[derive(Debug, Resource)]
+pub struct ThumbnailCache {
+    pub directory: PathBuf,
+    pub size: u32,
+    pub thumbnails: HashMap<PathBuf, Handle<Image>>,
+    pub pending: HashMap<PathBuf, Task<Option<RgbaImage>>>,
+    pub failed: HashSet<PathBuf>,
+    /// Label files changed after this instant are badged as edited.
+    pub started_at: SystemTime,
+    /// Set when thumbnails were dropped and the filmstrip's slots need
+    /// queueing again.
+    pub invalidated: bool,
+}
+
+impl ThumbnailCache {
+    pub fn new(output_path: &str, size: u32) -> Self {
+        Self {
+            directory: Path::new(output_path).join("thumbnails"),
+            size,
+            thumbnails: HashMap::new(),
+            pending: HashMap::new(),
+            failed: HashSet::new(),
+            started_at: SystemTime::now(),
+            invalidated: false,
+        }
+    }
+
+    /// Drops the thumbnail for an image whose labels changed, along with any
+    /// task still rendering the old labels.
+    pub fn invalidate(&mut self, image_path: &Path) {
+        self.thumbnails.remove(image_path);
+        self.pending.remove(image_path);
+        self.failed.remove(image_path);
+        self.invalidated = true;
+    }
+
+    /// Drops thumbnails and tasks for images outside the filmstrip's window,
+    /// so the in-memory cache stays the size of the filmstrip. Dropped
+    /// thumbnails are read back from disk when their images return.
+    pub fn retain(&mut self, window: &HashSet<PathBuf>) {
+        self.thumbnails
+            .retain(|image_path, _| window.contains(image_path));
+        self.pending
+            .retain(|image_path, _| window.contains(image_path));
+    }
+
+    pub fn is_known(&self, image_path: &Path) -> bool {
+        self.thumbnails.contains_key(image_path)
+            || self.pending.contains_key(image_path)
+            || self.failed.contains(image_path)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ThumbnailBadge {
+    Unlabeled,
+    Edited,
+    Approved,
+    NeedsFix,
+}
+
+impl ThumbnailBadge {
+    pub fn for_pair(
+        pair: &ImageLabelPair,
+        started_at: SystemTime,
+        review_status: ReviewStatus,
+    ) -> Vec<ThumbnailBadge> {
+        let mut badges = Vec::new();
+
+        match &pair.label_file {
+            Some(label_file) if !label_file.entries.is_empty() => {
+                if modified_at(Path::new(&label_file.path)).is_some_and(|time| time > started_at) {
+                    badges.push(ThumbnailBadge::Edited);
+                }
+            }
+            _ => badges.push(ThumbnailBadge::Unlabeled),
+        }
+
+        match review_status {
+            ReviewStatus::Unreviewed => {}
+            ReviewStatus::Approved => badges.push(ThumbnailBadge::Approved),
+            ReviewStatus::NeedsFix => badges.push(ThumbnailBadge::NeedsFix),
+        }
+
+        badges
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ThumbnailBadge::Unlabeled => "U",
+            ThumbnailBadge::Edited => "E",
+            ThumbnailBadge::Approved => "A",
+            ThumbnailBadge::NeedsFix => "!",
+        }
+    }
+}
+
+/// Everything a background task needs to produce one thumbnail.
+#[derive(Debug, Clone)]
+pub struct ThumbnailRequest {
+    pub image_path: PathBuf,
+    pub label_path: Option<PathBuf>,
+    pub cache_path: PathBuf,
+    pub entries: Vec<YoloEntry>,
+    pub class_colors: Vec<Srgba>,
+    pub size: u32,
+}
+
+impl ThumbnailRequest {
+    pub fn new(
+        pair: &ImageLabelPair,
+        directory: &Path,
+        class_colors: &[Srgba],
+        size: u32,
+    ) -> Option<Self> {
+        let image_path = pair.image_path.clone()?;
+        let cache_path = directory.join(cache_file_name(&pair.name, &image_path));
+
+        Some(Self {
+            image_path,
+            label_path: pair
+                .label_file
+                .as_ref()
+                .map(|label_file| PathBuf::from(&label_file.path)),
+            cache_path,
+            entries: pair
+                .label_file
+                .as_ref()
+                .map(|label_file| label_file.entries.clone())
+                .unwrap_or_default(),
+            class_colors: class_colors.to_vec(),
+            size,
+        })
+    }
+
+    /// Reads the cached thumbnail when it is newer than both its image and
+    /// labels, otherwise renders and stores a new one.
+    pub fn load_or_generate(&self) -> Option<RgbaImage> {
+        if self.cache_is_fresh() {
+            if let Ok(cached) = image::open(&self.cache_path) {
+                return Some(cached.to_rgba8());
+            }
+        }
+
+        let mut thumbnail = image::open(&self.image_path)
+            .ok()?
+            .thumbnail(self.size, self.size)
+            .to_rgba8();
+
+        for entry in self.entries.iter() {
+            let color = self
+                .class_colors
+                .get(entry.class as usize)
+                .map_or([255, 255, 255, 255], |color| color.to_u8_array());
+            draw_entry_outline(&mut thumbnail, entry, color);
+        }
+
+        if std::fs::create_dir_all(self.cache_path.parent()?).is_ok() {
+            let _ = thumbnail.save(&self.cache_path);
+        }
+
+        Some(thumbnail)
+    }
+
+    fn cache_is_fresh(&self) -> bool {
+        let cached_at = match modified_at(&self.cache_path) {
+            Some(time) => time,
+            None => return false,
+        };
+
+        [Some(self.image_path.as_path()), self.label_path.as_deref()]
+            .into_iter()
+            .flatten()
+            .all(|source| modified_at(source).is_some_and(|time| time <= cached_at))
+    }
+}
+
+/// Images with the same stem in different folders get their own cache
+/// file, keyed by a hash of the full image path.
+fn cache_file_name(name: &str, image_path: &Path) -> String {
+    // FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+    let hash = image_path
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    format!("{}-{:016x}.png", name, hash)
+}
+
+pub fn thumbnail_to_bevy_image(thumbnail: RgbaImage) -> Image {
+    let size = Extent3d {
+        width: thumbnail.width(),
+        height: thumbnail.height(),
+        depth_or_array_layers: 1,
+    };
+
+    Image::new(
+        size,
+        TextureDimension::D2,
+        thumbnail.into_raw(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn draw_entry_outline(thumbnail: &mut RgbaImage, entry: &YoloEntry, color: [u8; 4]) {
+    let (width, height) = (thumbnail.width() as f32, thumbnail.height() as f32);
+    if width < 1.0 || height < 1.0 {
+        return;
+    }
+
+    let left = ((entry.x_center - entry.width / 2.0) * width).clamp(0.0, width - 1.0) as u32;
+    let right = ((entry.x_center + entry.width / 2.0) * width).clamp(0.0, width - 1.0) as u32;
+    let top = ((entry.y_center - entry.height / 2.0) * height).clamp(0.0, height - 1.0) as u32;
+    let bottom = ((entry.y_center + entry.height / 2.0) * height).clamp(0.0, height - 1.0) as u32;
+
+    for x in left..=right {
+        thumbnail.put_pixel(x, top, image::Rgba(color));
+        thumbnail.put_pixel(x, bottom, image::Rgba(color));
+    }
+
+    for y in top..=bottom {
+        thumbnail.put_pixel(left, y, image::Rgba(color));
+        thumbnail.put_pixel(right, y, image::Rgba(color));
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use yolo_io::YoloFile;
+
+    use super::*;
+
+    fn pair_with_boxes(label_path: &str, boxes: usize) -> ImageLabelPair {
+        ImageLabelPair {
+            name: "a".to_string(),
+            image_path: None,
+            label_file: Some(YoloFile {
+                stem: "a".to_string(),
+                path: label_path.to_string(),
+                entries: vec![
+                    YoloEntry {
+                        class: 0,
+                        x_center: 0.5,
+                        y_center: 0.5,
+                        width: 0.1,
+                        height: 0.1,
+                    };
+                    boxes
+                ],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_cache_file_names_differ_by_image_path() {
+        let first = cache_file_name("sprite", Path::new("a/sprite.png"));
+        let second = cache_file_name("sprite", Path::new("b/sprite.png"));
+
+        assert_ne!(first, second);
+        assert!(first.starts_with("sprite-") && first.ends_with(".png"));
+        assert_eq!(first, cache_file_name("sprite", Path::new("a/sprite.png")));
+    }
+
+    #[test]
+    fn test_badges_for_pair() {
+        // Only the label file's modification time matters, any file will do.
+        let label_path = std::env::current_exe().unwrap();
+        let label_path = label_path.to_str().unwrap();
+        let later = SystemTime::now() + Duration::from_secs(3600);
+
+        let unlabeled = ImageLabelPair {
+            label_file: None,
+            ..pair_with_boxes(label_path, 0)
+        };
+        assert_eq!(
+            ThumbnailBadge::for_pair(&unlabeled, later, ReviewStatus::Unreviewed),
+            vec![ThumbnailBadge::Unlabeled]
+        );
+        assert_eq!(
+            ThumbnailBadge::for_pair(
+                &pair_with_boxes(label_path, 0),
+                later,
+                ReviewStatus::NeedsFix
+            ),
+            vec![ThumbnailBadge::Unlabeled, ThumbnailBadge::NeedsFix]
+        );
+        assert_eq!(
+            ThumbnailBadge::for_pair(
+                &pair_with_boxes(label_path, 1),
+                later,
+                ReviewStatus::Approved
+            ),
+            vec![ThumbnailBadge::Approved]
+        );
+        assert_eq!(
+            ThumbnailBadge::for_pair(
+                &pair_with_boxes(label_path, 1),
+                SystemTime::UNIX_EPOCH,
+                ReviewStatus::Unreviewed
+            ),
+            vec![ThumbnailBadge::Edited]
+        );
+    }
+
+    #[test]
+    fn test_retain_keeps_the_window() {
+        let mut cache = ThumbnailCache::new("output", 64);
+        for path in ["a.png", "b.png", "c.png"] {
+            cache
+                .thumbnails
+                .insert(PathBuf::from(path), Handle::default());
+        }
+
+        let window = HashSet::from([PathBuf::from("b.png"), PathBuf::from("d.png")]);
+        cache.retain(&window);
+
+        assert!(cache.is_known(Path::new("b.png")));
+        assert!(!cache.is_known(Path::new("a.png")));
+        assert!(!cache.is_known(Path::new("c.png")));
+    }
+}
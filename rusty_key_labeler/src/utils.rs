@@ -125,3 +125,21 @@ pub fn default_hide() -> Visibility {
 pub fn default_main_layer() -> RenderLayers {
     MAIN_LAYER
 }
+
+/// Converts a world position to image pixel coordinates, with the origin at
+/// the image's top-left corner and y pointing down.
+pub fn world_to_image_pixel(world: Vec2, image_size: Vec2) -> Vec2 {
+    Vec2::new(world.x + image_size.x / 2.0, image_size.y / 2.0 - world.y)
+}
+
+pub fn image_pixel_to_world(pixel: Vec2, image_size: Vec2) -> Vec2 {
+    Vec2::new(pixel.x - image_size.x / 2.0, image_size.y / 2.0 - pixel.y)
+}
+
+/// The raw pixels of an 8-bit RGBA image, the format PNG sprite sheets load as.
+pub fn rgba8_data(image: &Image) -> Option<&[u8]> {
+    match image.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => Some(&image.data),
+        _ => None,
+    }
+}